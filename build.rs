@@ -1,3 +1,10 @@
+use std::path::PathBuf;
+
 fn main() {
-    tonic_build::compile_protos("proto/solana.proto").unwrap();
-}
\ No newline at end of file
+    // The descriptor set backs the gRPC reflection service of the server
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    tonic_build::configure()
+        .file_descriptor_set_path(out_dir.join("solana_descriptor.bin"))
+        .compile_protos(&["proto/solana.proto"], &["proto"])
+        .unwrap();
+}
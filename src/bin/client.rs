@@ -1,14 +1,88 @@
 use solana::solana_service_client::SolanaServiceClient;
-use solana::{AirdropRequest, BalanceRequest, CreateWalletRequest, SendSolRequest, GreetRequest
+use solana::{AirdropRequest, BalanceRequest, CreateWalletRequest, SendSolRequest, GreetRequest,
+    ListNetworksRequest, ImportWalletRequest, ListWalletsRequest, UnlockWalletRequest,
+    LockWalletRequest, DeleteWalletRequest, Simulation, ComputeBudget, Commitment,
+    TransactionResult, TransactionStatusRequest, CreateNonceAccountRequest, GetNonceAccountRequest,
+    AdvanceNonceAccountRequest, WithdrawNonceAccountRequest, BuildTransferRequest,
+    SubmitSignedTransactionRequest, SendSolBatchRequest, Payout, EstimateFeeRequest,
+    estimate_fee_request::Call, CreateLookupTableRequest, ExtendLookupTableRequest,
+    DeactivateLookupTableRequest, CloseLookupTableRequest, GetLookupTableRequest,
+    GetAccountInfoRequest, GetTransactionHistoryRequest, HistoryEntry, GetTransactionRequest,
+    DecodedInstruction, GetBalancesRequest, GetClusterInfoRequest
 };
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::signature::{read_keypair_file, Keypair};
+use solana_sdk::transaction::VersionedTransaction;
 use std::env;
-use std::fs::File;
-use std::io::Write;
+use std::fs;
+use tonic::metadata::{Ascii, MetadataValue};
+use tonic::service::{interceptor::InterceptedService, Interceptor};
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Identity};
+use tonic::{Request, Status};
 
 pub mod solana {
     tonic::include_proto!("solana");
 }
 
+/// Attaches the caller's credential to every call: `SANDPIT_API_KEY` is sent as
+/// `x-api-key`, otherwise `SANDPIT_TOKEN` is sent as a bearer token.
+#[derive(Clone)]
+struct Credential(Option<(&'static str, MetadataValue<Ascii>)>);
+
+impl Credential {
+    fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
+        if let Ok(key) = env::var("SANDPIT_API_KEY") {
+            return Ok(Self(Some(("x-api-key", key.parse()?))));
+        }
+        if let Ok(token) = env::var("SANDPIT_TOKEN") {
+            return Ok(Self(Some(("authorization", format!("Bearer {}", token).parse()?))));
+        }
+        Ok(Self(None))
+    }
+}
+
+impl Interceptor for Credential {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some((key, value)) = &self.0 {
+            request.metadata_mut().insert(*key, value.clone());
+        }
+        Ok(request)
+    }
+}
+
+type Client = SolanaServiceClient<InterceptedService<Channel, Credential>>;
+
+/// Connects to the server named by `SANDPIT_SERVER`. TLS is configured with
+/// `SANDPIT_CA_CERT` (server CA), `SANDPIT_TLS_DOMAIN` (expected server name) and,
+/// for mTLS, `SANDPIT_CLIENT_CERT` plus `SANDPIT_CLIENT_KEY`.
+async fn connect() -> Result<Client, Box<dyn std::error::Error>> {
+    let url = env::var("SANDPIT_SERVER").unwrap_or_else(|_| "http://[::1]:50051".to_string());
+    let mut endpoint = Channel::from_shared(url)?;
+
+    let ca_cert = env::var("SANDPIT_CA_CERT").ok();
+    let client_cert = env::var("SANDPIT_CLIENT_CERT").ok();
+    if ca_cert.is_some() || client_cert.is_some() {
+        let mut tls = ClientTlsConfig::new();
+        if let Some(path) = ca_cert {
+            tls = tls.ca_certificate(Certificate::from_pem(fs::read(path)?));
+        }
+        if let Some(path) = client_cert {
+            let key = env::var("SANDPIT_CLIENT_KEY")
+                .map_err(|_| "SANDPIT_CLIENT_KEY must be set together with SANDPIT_CLIENT_CERT")?;
+            tls = tls.identity(Identity::from_pem(fs::read(path)?, fs::read(key)?));
+        }
+        if let Ok(domain) = env::var("SANDPIT_TLS_DOMAIN") {
+            tls = tls.domain_name(domain);
+        }
+        endpoint = endpoint.tls_config(tls)?;
+    }
+
+    // Connected on first use, so offline commands such as `sign` work without a server
+    let channel = endpoint.connect_lazy();
+    Ok(SolanaServiceClient::with_interceptor(channel, Credential::from_env()?))
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args: Vec<String> = env::args().collect();
@@ -17,129 +91,825 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         std::process::exit(1);
     }
     let command = &args[1];
-    let mut client = SolanaServiceClient::connect("http://[::1]:50051").await?;
+    let mut client = connect().await?;
     match command.as_str() {
         "get-balance" => {
             if args.len() != 4 {
                 eprintln!("Usage: {} get_balance <network> <wallet-address>", args[0]);
                 std::process::exit(1);
             }
-            let network = args[2].as_str();
+            let network = args[2].clone();
             let wallet_address = args[3].clone();
-            let network = match network {
-                "devnet" => "devnet",
-                "testnet" => "testnet",
-                "mainnet" => "mainnet",
-                _ => {
-                    eprintln!("Invalid network. Use 'devnet', 'testnet', or 'mainnet'.");
-                    std::process::exit(1);
-                }
-            };
-            let request = tonic::Request::new(BalanceRequest {  network: network.to_string(), wallet_address });
+            let request = tonic::Request::new(BalanceRequest { network, wallet_address });
             let response = client.get_balance(request).await?;
             println!("Wallet balance: {} lamports", response.into_inner().balance);
         },
+        "get-balances" => {
+            let mut args = args.clone();
+            let commitment = take_commitment(&mut args);
+            let file = take_option(&mut args, "--file");
+            if args.len() < 3 || (args.len() == 3 && file.is_none()) {
+                eprintln!("Usage: {} get-balances <network> [<address>...] [--file <addresses-file>] {}", args[0], COMMITMENT_USAGE);
+                std::process::exit(1);
+            }
+            let mut wallet_addresses = args[3..].to_vec();
+            if let Some(file) = file {
+                // One address per line
+                let contents = fs::read_to_string(file)?;
+                wallet_addresses.extend(contents.lines().map(str::trim).filter(|line| !line.is_empty()).map(String::from));
+            }
+            let request = tonic::Request::new(GetBalancesRequest {
+                network: args[2].clone(),
+                wallet_addresses,
+                commitment: commitment.into(),
+            });
+            let response = client.get_balances(request).await?.into_inner();
+            for balance in &response.balances {
+                if balance.error.is_empty() {
+                    println!("{}: {} lamports", balance.address, balance.lamports);
+                } else {
+                    println!("{}: {}", balance.address, balance.error);
+                }
+            }
+            println!("Read at slot {}", response.slot);
+        },
         "create-wallet" => {
-            let request = tonic::Request::new(CreateWalletRequest {});
+            if args.len() != 3 {
+                eprintln!("Usage: {} create-wallet <passphrase>", args[0]);
+                std::process::exit(1);
+            }
+            // The wallet is kept in the server keystore
+            let passphrase = args[2].clone();
+            let request = tonic::Request::new(CreateWalletRequest { passphrase });
             let response = client.create_wallet(request).await?;
             let response = response.into_inner();
-            println!("New wallet created:");
+            println!("New wallet stored on the server:");
+            println!("Wallet ID: {}", response.wallet_id);
             println!("Public Key: {}", response.public_key);
-            println!("Secret Key: {}", response.secret_key);
-
-            // Save the credentials to a local file named after the public key
-            let filename = format!("{}.txt", response.public_key);
-            let mut file = File::create(&filename)?;
-            writeln!(file, "Public Key: {}", response.public_key)?;
-            writeln!(file, "Secret Key: {}", response.secret_key)?;
-            println!("Credentials saved to {}", filename);
         },
         "request-airdrop" => {
             if args.len() != 5 {
                 eprintln!("Usage: {} request-airdrop <network> <wallet-address> <amount>", args[0]);
                 std::process::exit(1);
             }
-            let network = args[2].as_str();
+            let network = args[2].clone();
             let wallet_address = args[3].clone();
             let amount: u64 = args[4].parse().expect("Invalid amount");
-            let network = match network {
-                "devnet" => "devnet",
-                "testnet" => "testnet",
-                "mainnet" => {
-                    eprintln!("Faucet is not available on mainnet.");
-                    std::process::exit(1);
-                }
-                _ => {
-                    eprintln!("Invalid network. Use 'devnet', 'testnet', or 'mainnet'.");
-                    std::process::exit(1);
-                }
-            };
 
-            let request = tonic::Request::new(AirdropRequest { network: network.to_string(), wallet_address, amount });
+            let request = tonic::Request::new(AirdropRequest { network, wallet_address, amount });
             let response = client.request_airdrop(request).await?;
             println!("Airdrop requested. Transaction signature: {}", response.into_inner().signature);
         },
         "send-sol" => {
-            if args.len() != 7 {
-                eprintln!("Usage: {} send-sol <network> <from-address> <to-address> <amount> <from-secret-key>", args[0]);
+            let mut args = args.clone();
+            let compute_budget = take_compute_budget(&mut args);
+            let commitment = take_commitment(&mut args);
+            let dry_run = take_flag(&mut args, "--dry-run");
+            let no_wait = take_flag(&mut args, "--no-wait");
+            let nonce_address = take_option(&mut args, "--nonce").unwrap_or_default();
+            let nonce_authority_wallet_id = take_option(&mut args, "--nonce-authority").unwrap_or_default();
+            let memo = take_option(&mut args, "--memo").unwrap_or_default();
+            if args.len() != 6 {
+                eprintln!("Usage: {} send-sol <network> <wallet-id> <to-address> <amount> [--dry-run | --no-wait] [--nonce <nonce-address> [--nonce-authority <wallet-id>]] [--memo <text>] {} {}", args[0], COMPUTE_BUDGET_USAGE, COMMITMENT_USAGE);
                 std::process::exit(1);
             }
-            let network = args[2].as_str();
-            let from_address = args[3].clone();
+            let network = args[2].clone();
+            let wallet_id = args[3].clone();
             let to_address = args[4].clone();
             let amount: u64 = args[5].parse().expect("Invalid amount");
-            let from_secret_key = args[6].clone();
-            let network = match network {
-                "devnet" => "devnet",
-                "testnet" => "testnet",
-                "mainnet" => "mainnet",
-                _ => {
-                    eprintln!("Invalid network. Use 'devnet', 'testnet', or 'mainnet'.");
-                    std::process::exit(1);
-                }
-            };
 
             let request = tonic::Request::new(SendSolRequest {
-                from_address,
                 to_address,
                 amount,
-                rpc_url: network.to_string(),
-                from_secret_key,
+                network,
+                wallet_id,
+                dry_run,
+                compute_budget,
+                commitment: commitment.into(),
+                no_wait,
+                nonce_address,
+                nonce_authority_wallet_id,
+                memo: memo.into_bytes(),
             });
-            let response = client.send_sol(request).await?;
-            println!("SOL sent. Transaction signature: {}", response.into_inner().signature);
+            let response = client.send_sol(request).await?.into_inner();
+            if let Some(simulation) = &response.simulation {
+                print_simulation(simulation);
+            }
+            if let Some(result) = &response.result {
+                println!("SOL sent.");
+                print_transaction_result(result);
+            }
+            println!("Fee: {} lamports", response.fee);
         },
-        "greet" => {
+        "send-sol-batch" => {
+            let mut args = args.clone();
+            let compute_budget = take_compute_budget(&mut args);
+            let commitment = take_commitment(&mut args);
+            let lookup_table_address = take_option(&mut args, "--lookup-table").unwrap_or_default();
             if args.len() != 5 {
-                eprintln!("Usage: {} greet <network> <payer-secret-key> <seed>", args[0]);
+                eprintln!("Usage: {} send-sol-batch <network> <wallet-id> <payouts-file> [--lookup-table <address>] {} {}", args[0], COMPUTE_BUDGET_USAGE, COMMITMENT_USAGE);
+                eprintln!("Each line of the payouts file is '<to-address> <amount> [<memo>]'.");
                 std::process::exit(1);
             }
-            let network: &str = args[2].as_str();
-            let payer_secret_key = args[3].clone();
-            let seed: String = args[4].clone();
-            let network = match network {
-                "devnet" => "devnet",
-                "testnet" => "testnet",
-                "mainnet" => "mainnet",
+            let network = args[2].clone();
+            let wallet_id = args[3].clone();
+            let payouts = fs::read_to_string(&args[4])?
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| {
+                    let mut fields = line.trim().splitn(3, char::is_whitespace);
+                    let to_address = fields.next().unwrap_or_default().to_string();
+                    let amount: u64 = fields.next().and_then(|amount| amount.parse().ok()).expect("Invalid payout line");
+                    let memo = fields.next().unwrap_or_default().trim().as_bytes().to_vec();
+                    Payout { to_address, amount, memo }
+                })
+                .collect();
+
+            let request = tonic::Request::new(SendSolBatchRequest {
+                network,
+                wallet_id,
+                payouts,
+                compute_budget,
+                commitment: commitment.into(),
+                lookup_table_address,
+            });
+            let response = client.send_sol_batch(request).await?.into_inner();
+            for result in &response.results {
+                if result.error.is_empty() {
+                    println!("{} {}: sent in {}", result.to_address, result.amount, result.signature);
+                } else {
+                    println!("{} {}: failed in {}: {}", result.to_address, result.amount, result.signature, result.error);
+                }
+            }
+            println!("{} transaction(s), fee: {} lamports", response.transactions.len(), response.fee);
+        },
+        "estimate-fee" => {
+            let mut args = args.clone();
+            let compute_budget = take_compute_budget(&mut args);
+            let nonce_address = take_option(&mut args, "--nonce").unwrap_or_default();
+            let nonce_authority_wallet_id = take_option(&mut args, "--nonce-authority").unwrap_or_default();
+            let memo = take_option(&mut args, "--memo").unwrap_or_default();
+            let call = match (args.get(2).map(String::as_str), args.len()) {
+                (Some("send-sol"), 7) => Call::SendSol(SendSolRequest {
+                    network: args[3].clone(),
+                    wallet_id: args[4].clone(),
+                    to_address: args[5].clone(),
+                    amount: args[6].parse().expect("Invalid amount"),
+                    compute_budget,
+                    nonce_address,
+                    nonce_authority_wallet_id,
+                    memo: memo.into_bytes(),
+                    ..SendSolRequest::default()
+                }),
+                (Some("greet"), 6) => Call::Greet(GreetRequest {
+                    network: args[3].clone(),
+                    wallet_id: args[4].clone(),
+                    seed: args[5].clone(),
+                    compute_budget,
+                    ..GreetRequest::default()
+                }),
                 _ => {
-                    eprintln!("Invalid network. Use 'devnet', 'testnet', or 'mainnet'.");
+                    eprintln!("Usage: {} estimate-fee send-sol <network> <wallet-id> <to-address> <amount> [--nonce <nonce-address> [--nonce-authority <wallet-id>]] [--memo <text>] {}", args[0], COMPUTE_BUDGET_USAGE);
+                    eprintln!("       {} estimate-fee greet <network> <wallet-id> <seed> {}", args[0], COMPUTE_BUDGET_USAGE);
                     std::process::exit(1);
                 }
             };
 
+            let request = tonic::Request::new(EstimateFeeRequest { call: Some(call) });
+            let response = client.estimate_fee(request).await?.into_inner();
+            println!("Transactions: {}", response.transactions);
+            println!("Base fee: {} lamports", response.base_fee);
+            println!("Priority fee: {} lamports", response.priority_fee);
+            if response.rent != 0 {
+                println!("Rent: {} lamports", response.rent);
+            }
+            println!("Total: {} lamports", response.total);
+        },
+        "greet" => {
+            let mut args = args.clone();
+            let compute_budget = take_compute_budget(&mut args);
+            let commitment = take_commitment(&mut args);
+            let dry_run = take_flag(&mut args, "--dry-run");
+            if args.len() != 5 {
+                eprintln!("Usage: {} greet <network> <wallet-id> <seed> [--dry-run] {} {}", args[0], COMPUTE_BUDGET_USAGE, COMMITMENT_USAGE);
+                std::process::exit(1);
+            }
+            let network = args[2].clone();
+            let wallet_id = args[3].clone();
+            let seed: String = args[4].clone();
+
             let request = tonic::Request::new(GreetRequest {
-                network: network.to_string(),
-                payer_secret_key,
+                network,
                 seed,
+                wallet_id,
+                dry_run,
+                compute_budget,
+                commitment: commitment.into(),
+            });
+            let response = client.greet(request).await?.into_inner();
+            if let Some(simulation) = &response.simulation {
+                print_simulation(simulation);
+            }
+            if let Some(result) = &response.create_account {
+                println!("Created greeting account {}", response.greeted_address);
+                print_transaction_result(result);
+            }
+            if let Some(result) = &response.result {
+                println!("Greeted {} ({} time(s))", response.greeted_address, response.counter);
+                print_transaction_result(result);
+            }
+            println!("Fee: {} lamports", response.fee);
+        },
+        "list-networks" => {
+            let request = tonic::Request::new(ListNetworksRequest {});
+            let response = client.list_networks(request).await?;
+            for network in response.into_inner().networks {
+                println!(
+                    "{}: rpc={} ws={} faucet={}",
+                    network.name, network.rpc_url, network.ws_url, network.faucet
+                );
+            }
+        },
+        "cluster-info" => {
+            let mut args = args.clone();
+            let commitment = take_commitment(&mut args);
+            if args.len() != 3 {
+                eprintln!("Usage: {} cluster-info <network> {}", args[0], COMMITMENT_USAGE);
+                std::process::exit(1);
+            }
+            let request = tonic::Request::new(GetClusterInfoRequest {
+                network: args[2].clone(),
+                commitment: commitment.into(),
             });
-            let response = client.greet(request).await?;
-            println!("Greet transaction signature: {}", response.into_inner().signature);
+            let response = client.get_cluster_info(request).await?.into_inner();
+            println!("Network: {} ({})", response.network, response.rpc_url);
+            println!("Version: {} (feature set {})", response.version, response.feature_set);
+            println!("Slot: {}", response.slot);
+            println!("Block height: {}", response.block_height);
+            if let Some(epoch_info) = &response.epoch_info {
+                println!(
+                    "Epoch: {} (slot {} of {})",
+                    epoch_info.epoch, epoch_info.slot_index, epoch_info.slots_in_epoch
+                );
+            }
+            if response.healthy {
+                println!("Health: ok");
+            } else {
+                println!("Health: {}", response.health_error);
+            }
+        },
+        "import-wallet" => {
+            if args.len() != 4 {
+                eprintln!("Usage: {} import-wallet <secret-key> <passphrase>", args[0]);
+                std::process::exit(1);
+            }
+            let secret_key = args[2].clone();
+            let passphrase = args[3].clone();
+
+            let request = tonic::Request::new(ImportWalletRequest { secret_key, passphrase });
+            let response = client.import_wallet(request).await?.into_inner();
+            println!("Wallet imported. Wallet ID: {} Public Key: {}", response.wallet_id, response.public_key);
+        },
+        "list-wallets" => {
+            let request = tonic::Request::new(ListWalletsRequest {});
+            let response = client.list_wallets(request).await?;
+            for wallet in response.into_inner().wallets {
+                let state = if wallet.unlocked { "unlocked" } else { "locked" };
+                println!("{}: {} ({})", wallet.wallet_id, wallet.public_key, state);
+            }
+        },
+        "unlock-wallet" => {
+            if args.len() != 4 {
+                eprintln!("Usage: {} unlock-wallet <wallet-id> <passphrase>", args[0]);
+                std::process::exit(1);
+            }
+            let wallet_id = args[2].clone();
+            let passphrase = args[3].clone();
+
+            let request = tonic::Request::new(UnlockWalletRequest { wallet_id, passphrase });
+            client.unlock_wallet(request).await?;
+            println!("Wallet {} unlocked", args[2]);
+        },
+        "lock-wallet" => {
+            if args.len() != 3 {
+                eprintln!("Usage: {} lock-wallet <wallet-id>", args[0]);
+                std::process::exit(1);
+            }
+            let wallet_id = args[2].clone();
+
+            let request = tonic::Request::new(LockWalletRequest { wallet_id });
+            client.lock_wallet(request).await?;
+            println!("Wallet {} locked", args[2]);
+        },
+        "delete-wallet" => {
+            if args.len() != 4 {
+                eprintln!("Usage: {} delete-wallet <wallet-id> <passphrase>", args[0]);
+                std::process::exit(1);
+            }
+            let wallet_id = args[2].clone();
+            let passphrase = args[3].clone();
+
+            let request = tonic::Request::new(DeleteWalletRequest { wallet_id, passphrase });
+            client.delete_wallet(request).await?;
+            println!("Wallet {} deleted", args[2]);
+        },
+        "create-nonce" => {
+            let mut args = args.clone();
+            let commitment = take_commitment(&mut args);
+            let authority = take_option(&mut args, "--authority").unwrap_or_default();
+            let lamports = take_option(&mut args, "--lamports").map(|lamports| lamports.parse().expect("Invalid lamports"));
+            if args.len() != 4 {
+                eprintln!("Usage: {} create-nonce <network> <wallet-id> [--authority <address>] [--lamports <lamports>] {}", args[0], COMMITMENT_USAGE);
+                std::process::exit(1);
+            }
+            let request = tonic::Request::new(CreateNonceAccountRequest {
+                network: args[2].clone(),
+                wallet_id: args[3].clone(),
+                authority,
+                lamports: lamports.unwrap_or_default(),
+                commitment: commitment.into(),
+            });
+            let response = client.create_nonce_account(request).await?.into_inner();
+            println!("Nonce account: {}", response.nonce_address);
+            if let Some(result) = &response.result {
+                print_transaction_result(result);
+            }
+        },
+        "get-nonce" => {
+            if args.len() != 4 {
+                eprintln!("Usage: {} get-nonce <network> <nonce-address>", args[0]);
+                std::process::exit(1);
+            }
+            let request = tonic::Request::new(GetNonceAccountRequest {
+                network: args[2].clone(),
+                nonce_address: args[3].clone(),
+            });
+            let response = client.get_nonce_account(request).await?.into_inner();
+            println!("Nonce: {}", response.nonce);
+            println!("Authority: {}", response.authority);
+            println!("Balance: {} lamports", response.lamports);
+            println!("Fee: {} lamports per signature", response.lamports_per_signature);
+        },
+        "advance-nonce" => {
+            let mut args = args.clone();
+            let commitment = take_commitment(&mut args);
+            if args.len() != 5 {
+                eprintln!("Usage: {} advance-nonce <network> <nonce-address> <authority-wallet-id> {}", args[0], COMMITMENT_USAGE);
+                std::process::exit(1);
+            }
+            let request = tonic::Request::new(AdvanceNonceAccountRequest {
+                network: args[2].clone(),
+                nonce_address: args[3].clone(),
+                wallet_id: args[4].clone(),
+                commitment: commitment.into(),
+            });
+            let response = client.advance_nonce_account(request).await?.into_inner();
+            println!("New nonce: {}", response.nonce);
+            if let Some(result) = &response.result {
+                print_transaction_result(result);
+            }
+        },
+        "withdraw-nonce" => {
+            let mut args = args.clone();
+            let commitment = take_commitment(&mut args);
+            let memo = take_option(&mut args, "--memo").unwrap_or_default();
+            if args.len() != 7 {
+                eprintln!("Usage: {} withdraw-nonce <network> <nonce-address> <authority-wallet-id> <to-address> <amount> [--memo <text>] {}", args[0], COMMITMENT_USAGE);
+                std::process::exit(1);
+            }
+            let request = tonic::Request::new(WithdrawNonceAccountRequest {
+                network: args[2].clone(),
+                nonce_address: args[3].clone(),
+                wallet_id: args[4].clone(),
+                to_address: args[5].clone(),
+                amount: args[6].parse().expect("Invalid amount"),
+                commitment: commitment.into(),
+                memo: memo.into_bytes(),
+            });
+            let response = client.withdraw_nonce_account(request).await?.into_inner();
+            if let Some(result) = &response.result {
+                print_transaction_result(result);
+            }
+        },
+        "create-lookup-table" => {
+            let mut args = args.clone();
+            let commitment = take_commitment(&mut args);
+            if args.len() < 4 {
+                eprintln!("Usage: {} create-lookup-table <network> <wallet-id> [<address>...] {}", args[0], COMMITMENT_USAGE);
+                std::process::exit(1);
+            }
+            let request = tonic::Request::new(CreateLookupTableRequest {
+                network: args[2].clone(),
+                wallet_id: args[3].clone(),
+                addresses: args[4..].to_vec(),
+                commitment: commitment.into(),
+            });
+            let response = client.create_lookup_table(request).await?.into_inner();
+            println!("Lookup table: {}", response.lookup_table_address);
+            for result in &response.results {
+                print_transaction_result(result);
+            }
+        },
+        "extend-lookup-table" => {
+            let mut args = args.clone();
+            let commitment = take_commitment(&mut args);
+            if args.len() < 6 {
+                eprintln!("Usage: {} extend-lookup-table <network> <lookup-table-address> <authority-wallet-id> <address>... {}", args[0], COMMITMENT_USAGE);
+                std::process::exit(1);
+            }
+            let request = tonic::Request::new(ExtendLookupTableRequest {
+                network: args[2].clone(),
+                lookup_table_address: args[3].clone(),
+                wallet_id: args[4].clone(),
+                addresses: args[5..].to_vec(),
+                commitment: commitment.into(),
+            });
+            let response = client.extend_lookup_table(request).await?.into_inner();
+            if response.results.is_empty() {
+                println!("All addresses are already in the table.");
+            }
+            for result in &response.results {
+                print_transaction_result(result);
+            }
+        },
+        "deactivate-lookup-table" => {
+            let mut args = args.clone();
+            let commitment = take_commitment(&mut args);
+            if args.len() != 5 {
+                eprintln!("Usage: {} deactivate-lookup-table <network> <lookup-table-address> <authority-wallet-id> {}", args[0], COMMITMENT_USAGE);
+                std::process::exit(1);
+            }
+            let request = tonic::Request::new(DeactivateLookupTableRequest {
+                network: args[2].clone(),
+                lookup_table_address: args[3].clone(),
+                wallet_id: args[4].clone(),
+                commitment: commitment.into(),
+            });
+            let response = client.deactivate_lookup_table(request).await?.into_inner();
+            if let Some(result) = &response.result {
+                print_transaction_result(result);
+            }
+        },
+        "close-lookup-table" => {
+            let mut args = args.clone();
+            let commitment = take_commitment(&mut args);
+            let recipient_address = take_option(&mut args, "--recipient").unwrap_or_default();
+            if args.len() != 5 {
+                eprintln!("Usage: {} close-lookup-table <network> <lookup-table-address> <authority-wallet-id> [--recipient <address>] {}", args[0], COMMITMENT_USAGE);
+                std::process::exit(1);
+            }
+            let request = tonic::Request::new(CloseLookupTableRequest {
+                network: args[2].clone(),
+                lookup_table_address: args[3].clone(),
+                wallet_id: args[4].clone(),
+                recipient_address,
+                commitment: commitment.into(),
+            });
+            let response = client.close_lookup_table(request).await?.into_inner();
+            if let Some(result) = &response.result {
+                print_transaction_result(result);
+            }
+        },
+        "get-lookup-table" => {
+            if args.len() != 4 {
+                eprintln!("Usage: {} get-lookup-table <network> <lookup-table-address>", args[0]);
+                std::process::exit(1);
+            }
+            let request = tonic::Request::new(GetLookupTableRequest {
+                network: args[2].clone(),
+                lookup_table_address: args[3].clone(),
+            });
+            let response = client.get_lookup_table(request).await?.into_inner();
+            print!("State: {:?}", response.state());
+            if response.deactivation_slot != 0 {
+                print!(" since slot {}", response.deactivation_slot);
+            }
+            if response.remaining_blocks != 0 {
+                print!(", closable in {} blocks", response.remaining_blocks);
+            }
+            println!();
+            if response.authority.is_empty() {
+                println!("Authority: none (frozen)");
+            } else {
+                println!("Authority: {}", response.authority);
+            }
+            println!("Addresses ({}):", response.addresses.len());
+            for (index, address) in response.addresses.iter().enumerate() {
+                println!("  {:>3} {}", index, address);
+            }
+        },
+        "get-account-info" => {
+            let mut args = args.clone();
+            let commitment = take_commitment(&mut args);
+            if args.len() != 4 {
+                eprintln!("Usage: {} get-account-info <network> <address> {}", args[0], COMMITMENT_USAGE);
+                std::process::exit(1);
+            }
+            let request = tonic::Request::new(GetAccountInfoRequest {
+                network: args[2].clone(),
+                address: args[3].clone(),
+                commitment: commitment.into(),
+            });
+            let response = client.get_account_info(request).await?.into_inner();
+            println!("Owner: {}", response.owner);
+            println!("Balance: {} lamports", response.lamports);
+            println!("Executable: {}", response.executable);
+            println!("Rent epoch: {}", response.rent_epoch);
+            println!("Slot: {}", response.slot);
+            if !response.layout.is_empty() {
+                let decoded: serde_json::Value = serde_json::from_str(&response.decoded)?;
+                println!("Decoded as {}:", response.layout);
+                println!("{}", serde_json::to_string_pretty(&decoded)?);
+            } else if !response.decode_error.is_empty() {
+                println!("Could not decode data: {}", response.decode_error);
+            }
+            println!("Data: {}", response.data);
+        },
+        "history" => {
+            let mut args = args.clone();
+            let commitment = take_commitment(&mut args);
+            let before = take_option(&mut args, "--before").unwrap_or_default();
+            let until = take_option(&mut args, "--until").unwrap_or_default();
+            let limit = take_option(&mut args, "--limit").map(|limit| limit.parse().expect("Invalid limit")).unwrap_or(0);
+            if args.len() != 4 {
+                eprintln!("Usage: {} history <network> <address> [--before <signature>] [--until <signature>] [--limit <count>] {}", args[0], COMMITMENT_USAGE);
+                std::process::exit(1);
+            }
+            let request = tonic::Request::new(GetTransactionHistoryRequest {
+                network: args[2].clone(),
+                address: args[3].clone(),
+                before,
+                until,
+                limit,
+                commitment: commitment.into(),
+            });
+            let response = client.get_transaction_history(request).await?.into_inner();
+            for entry in &response.entries {
+                print_history_entry(entry);
+            }
+            if !response.next_before.is_empty() {
+                println!("More: --before {}", response.next_before);
+            }
+        },
+        "get-transaction" => {
+            let mut args = args.clone();
+            let commitment = take_commitment(&mut args);
+            if args.len() != 4 {
+                eprintln!("Usage: {} get-transaction <network> <signature> {}", args[0], COMMITMENT_USAGE);
+                std::process::exit(1);
+            }
+            let request = tonic::Request::new(GetTransactionRequest {
+                network: args[2].clone(),
+                signature: args[3].clone(),
+                commitment: commitment.into(),
+            });
+            let response = client.get_transaction(request).await?.into_inner();
+            println!("Slot: {}", response.slot);
+            if response.block_time != 0 {
+                println!("Block time: {}", response.block_time);
+            }
+            println!("Fee: {} lamports", response.fee);
+            println!("Compute units consumed: {}", response.compute_units_consumed);
+            if !response.error.is_empty() {
+                println!("Transaction failed: {}", response.error);
+            }
+            println!("Instructions:");
+            for (index, instruction) in response.instructions.iter().enumerate() {
+                print_instruction(&format!("#{}", index), instruction);
+            }
+            println!("Balances:");
+            for balance in &response.balances {
+                println!("  {}: {} -> {} lamports", balance.address, balance.pre_lamports, balance.post_lamports);
+            }
+            println!("Logs:");
+            for log in &response.logs {
+                println!("  {}", log);
+            }
+        },
+        "build" => {
+            let mut args = args.clone();
+            let compute_budget = take_compute_budget(&mut args);
+            let nonce_address = take_option(&mut args, "--nonce").unwrap_or_default();
+            let nonce_authority = take_option(&mut args, "--nonce-authority").unwrap_or_default();
+            let memo = take_option(&mut args, "--memo").unwrap_or_default();
+            if args.len() != 7 {
+                eprintln!("Usage: {} build <network> <from-address> <to-address> <amount> <message-file> [--nonce <nonce-address> [--nonce-authority <address>]] [--memo <text>] {}", args[0], COMPUTE_BUDGET_USAGE);
+                std::process::exit(1);
+            }
+            let request = tonic::Request::new(BuildTransferRequest {
+                network: args[2].clone(),
+                from_address: args[3].clone(),
+                to_address: args[4].clone(),
+                amount: args[5].parse().expect("Invalid amount"),
+                compute_budget,
+                nonce_address,
+                nonce_authority,
+                memo: memo.into_bytes(),
+            });
+            let response = client.build_transfer(request).await?.into_inner();
+            fs::write(&args[6], &response.message)?;
+            println!("Unsigned message written to {}", args[6]);
+            println!("Blockhash: {}", response.blockhash);
+            if response.last_valid_block_height != 0 {
+                println!("Valid until block height {}", response.last_valid_block_height);
+            }
+            println!("Signers: {}", response.signers.join(", "));
+            println!("Fee: {} lamports", response.fee);
+        },
+        "sign" => {
+            if args.len() < 5 {
+                eprintln!("Usage: {} sign <message-file> <transaction-file> <keypair-file>...", args[0]);
+                std::process::exit(1);
+            }
+            let message = BASE64.decode(fs::read_to_string(&args[2])?.trim())?;
+            let message: VersionedMessage = bincode::deserialize(&message)?;
+            let keypairs = args[4..]
+                .iter()
+                .map(|path| read_keypair_file(path).map_err(|err| format!("{}: {}", path, err)))
+                .collect::<Result<Vec<Keypair>, _>>()?;
+            let keypairs: Vec<&Keypair> = keypairs.iter().collect();
+
+            let transaction = VersionedTransaction::try_new(message, keypairs.as_slice())?;
+            fs::write(&args[3], BASE64.encode(bincode::serialize(&transaction)?))?;
+            println!("Signed transaction written to {}", args[3]);
+            println!("Transaction signature: {}", transaction.signatures[0]);
+        },
+        "submit" => {
+            let mut args = args.clone();
+            let commitment = take_commitment(&mut args);
+            let no_wait = take_flag(&mut args, "--no-wait");
+            if args.len() != 4 {
+                eprintln!("Usage: {} submit <network> <transaction-file> [--no-wait] {}", args[0], COMMITMENT_USAGE);
+                std::process::exit(1);
+            }
+            let request = tonic::Request::new(SubmitSignedTransactionRequest {
+                network: args[2].clone(),
+                transaction: fs::read_to_string(&args[3])?.trim().to_string(),
+                commitment: commitment.into(),
+                no_wait,
+            });
+            let response = client.submit_signed_transaction(request).await?.into_inner();
+            if let Some(result) = &response.result {
+                print_transaction_result(result);
+            }
+        },
+        "transaction-status" => {
+            if args.len() < 4 {
+                eprintln!("Usage: {} transaction-status <network> <signature>...", args[0]);
+                std::process::exit(1);
+            }
+            let network = args[2].clone();
+            let signatures = args[3..].to_vec();
+
+            let request = tonic::Request::new(TransactionStatusRequest { network, signatures });
+            let response = client.get_transaction_status(request).await?;
+            for status in response.into_inner().statuses {
+                print!("{}: {:?}", status.signature, status.state());
+                if status.slot != 0 {
+                    print!(" in slot {}", status.slot);
+                }
+                if status.rebroadcasts != 0 {
+                    print!(", re-broadcast {} time(s)", status.rebroadcasts);
+                }
+                if !status.error.is_empty() {
+                    print!(": {}", status.error);
+                }
+                println!();
+                for memo in &status.memos {
+                    println!("  memo: {}", memo);
+                }
+            }
         },
         _ => {
-            eprintln!("Invalid command. Use 'get-balance', 'create-wallet', 'request-airdrop', 'send-sol' or greet.");
+            eprintln!("Invalid command. Use 'get-balance', 'get-balances', 'create-wallet', 'import-wallet', 'list-wallets', 'unlock-wallet', 'lock-wallet', 'delete-wallet', 'request-airdrop', 'send-sol', 'send-sol-batch', 'estimate-fee', 'greet', 'build', 'sign', 'submit', 'transaction-status', 'create-nonce', 'get-nonce', 'advance-nonce', 'withdraw-nonce', 'create-lookup-table', 'extend-lookup-table', 'deactivate-lookup-table', 'close-lookup-table', 'get-lookup-table', 'get-account-info', 'history', 'get-transaction', 'cluster-info' or 'list-networks'.");
             std::process::exit(1);
         },
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+const COMPUTE_BUDGET_USAGE: &str = "[--cu-limit <units>] [--cu-price <micro-lamports> | --auto-fee]";
+
+/// Removes `flag` from `args`, returning whether it was present.
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let present = args.iter().any(|arg| arg == flag);
+    args.retain(|arg| arg != flag);
+    present
+}
+
+/// Removes `option` and the value following it from `args`.
+fn take_option(args: &mut Vec<String>, option: &str) -> Option<String> {
+    let index = args.iter().position(|arg| arg == option)?;
+    if index + 1 >= args.len() {
+        eprintln!("Missing value for {}", option);
+        std::process::exit(1);
+    }
+    let value = args.remove(index + 1);
+    args.remove(index);
+    Some(value)
+}
+
+const COMMITMENT_USAGE: &str = "[--commitment processed|confirmed|finalized]";
+
+/// Parses `--commitment`, leaving the choice to the server when absent.
+fn take_commitment(args: &mut Vec<String>) -> Commitment {
+    match take_option(args, "--commitment").as_deref() {
+        None => Commitment::Unspecified,
+        Some("processed") => Commitment::Processed,
+        Some("confirmed") => Commitment::Confirmed,
+        Some("finalized") => Commitment::Finalized,
+        Some(other) => {
+            eprintln!("Invalid commitment: {}", other);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Parses the compute budget options, `None` when none were given.
+fn take_compute_budget(args: &mut Vec<String>) -> Option<ComputeBudget> {
+    let unit_limit = take_option(args, "--cu-limit").map(|limit| limit.parse().expect("Invalid compute unit limit"));
+    let unit_price = take_option(args, "--cu-price").map(|price| price.parse().expect("Invalid compute unit price"));
+    let auto_price = take_flag(args, "--auto-fee");
+    if unit_limit.is_none() && unit_price.is_none() && !auto_price {
+        return None;
+    }
+    Some(ComputeBudget {
+        unit_limit: unit_limit.unwrap_or_default(),
+        unit_price: unit_price.unwrap_or_default(),
+        auto_price,
+    })
+}
+
+fn print_transaction_result(result: &TransactionResult) {
+    println!("Transaction signature: {}", result.signature);
+    if result.confirmation_status() == Commitment::Unspecified {
+        println!("Submitted without waiting, check with transaction-status");
+        return;
+    }
+    println!("Slot: {} ({:?})", result.slot, result.confirmation_status());
+    if result.block_time != 0 {
+        println!("Block time: {}", result.block_time);
+    }
+    if !result.error.is_empty() {
+        println!("Transaction failed: {}", result.error);
+    }
+}
+
+fn print_history_entry(entry: &HistoryEntry) {
+    print!("{} slot {} ({:?})", entry.signature, entry.slot, entry.confirmation_status());
+    if entry.block_time != 0 {
+        print!(" at {}", entry.block_time);
+    }
+    println!();
+    if !entry.error.is_empty() {
+        println!("  Failed: {}", entry.error);
+    }
+    if entry.programs.is_empty() {
+        println!("  Transaction no longer available");
+        return;
+    }
+    println!("  Balance change: {} lamports (fee {})", entry.balance_change, entry.fee);
+    for transfer in &entry.transfers {
+        println!("  Transfer: {} -> {} {} lamports", transfer.from, transfer.to, transfer.lamports);
+    }
+    for memo in &entry.memos {
+        println!("  Memo: {}", memo);
+    }
+    println!("  Programs: {}", entry.programs.join(", "));
+}
+
+fn print_instruction(label: &str, instruction: &DecodedInstruction) {
+    let indent = "  ".repeat(instruction.stack_height as usize);
+    if instruction.name.is_empty() {
+        println!("{}{} {}", indent, label, instruction.program_id);
+        println!("{}  Accounts: {}", indent, instruction.accounts.join(", "));
+        println!("{}  Data: {}", indent, instruction.data);
+        if !instruction.parse_error.is_empty() {
+            println!("{}  Could not decode: {}", indent, instruction.parse_error);
+        }
+    } else {
+        println!("{}{} {} {}: {}", indent, label, instruction.program, instruction.name, instruction.parsed);
+    }
+    for (index, inner) in instruction.inner_instructions.iter().enumerate() {
+        print_instruction(&format!("{}.{}", label, index + 1), inner);
+    }
+}
+
+fn print_simulation(simulation: &Simulation) {
+    if simulation.error.is_empty() {
+        println!("Simulation succeeded");
+    } else {
+        println!("Simulation failed: {}", simulation.error);
+    }
+    println!("Compute units consumed: {}", simulation.units_consumed);
+    for balance in &simulation.balances {
+        println!(
+            "{}: {} -> {} lamports",
+            balance.address, balance.pre_lamports, balance.post_lamports
+        );
+    }
+    for log in &simulation.logs {
+        println!("  {}", log);
+    }
+}
@@ -0,0 +1,472 @@
+use crate::error::ServiceError;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use solana_sdk::hash::{hash, Hash};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+use tonic::metadata::MetadataMap;
+use tonic::{Request, Status};
+use tracing::{error, info, Span};
+
+/// Metadata key carrying a static API key.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Methods and networks a credential may use. `"*"` matches everything.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Permissions {
+    #[serde(default)]
+    pub methods: Vec<String>,
+    #[serde(default)]
+    pub networks: Vec<String>,
+}
+
+impl Permissions {
+    fn allows_method(&self, method: &str) -> bool {
+        self.methods
+            .iter()
+            .any(|allowed| allowed == "*" || allowed == method)
+    }
+
+    fn allows_network(&self, network: &str) -> bool {
+        self.networks
+            .iter()
+            .any(|allowed| allowed == "*" || allowed == network)
+    }
+
+    /// Narrows these permissions to the ones also requested by a token. A wildcard
+    /// in the request keeps everything the credential grants.
+    fn intersect(&self, requested: &Permissions) -> Permissions {
+        let narrow = |granted: &[String], requested: &[String], allows: &dyn Fn(&str) -> bool| {
+            if requested.iter().any(|value| value == "*") {
+                granted.to_vec()
+            } else {
+                requested
+                    .iter()
+                    .filter(|value| allows(value))
+                    .cloned()
+                    .collect()
+            }
+        };
+        Permissions {
+            methods: narrow(&self.methods, &requested.methods, &|method| {
+                self.allows_method(method)
+            }),
+            networks: narrow(&self.networks, &requested.networks, &|network| {
+                self.allows_network(network)
+            }),
+        }
+    }
+}
+
+/// The authenticated caller, attached to request extensions by the interceptor.
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub name: String,
+    pub permissions: Permissions,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiKeyConfig {
+    name: String,
+    key: String,
+    #[serde(flatten)]
+    permissions: Permissions,
+}
+
+#[derive(Debug, Deserialize)]
+struct JwtKeyConfig {
+    name: String,
+    algorithm: Algorithm,
+    /// Shared secret for HS256.
+    secret: Option<String>,
+    /// PEM encoded EC public key for ES256.
+    public_key: Option<PathBuf>,
+    issuer: Option<String>,
+    audience: Option<String>,
+    #[serde(flatten)]
+    permissions: Permissions,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CredentialsConfig {
+    #[serde(default)]
+    api_keys: Vec<ApiKeyConfig>,
+    #[serde(default)]
+    jwt_keys: Vec<JwtKeyConfig>,
+}
+
+struct ApiKey {
+    name: String,
+    /// Keys are compared by their SHA-256 so lookups do not leak key prefixes.
+    digest: Hash,
+    permissions: Permissions,
+}
+
+struct JwtKey {
+    name: String,
+    key: DecodingKey,
+    validation: Validation,
+    permissions: Permissions,
+}
+
+#[derive(Default)]
+struct Credentials {
+    api_keys: Vec<ApiKey>,
+    jwt_keys: Vec<JwtKey>,
+}
+
+/// Optional narrowing of permissions carried inside a JWT.
+#[derive(Debug, Deserialize)]
+struct Claims {
+    sub: Option<String>,
+    methods: Option<Vec<String>>,
+    networks: Option<Vec<String>>,
+}
+
+impl Credentials {
+    fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let config: CredentialsConfig = toml::from_str(&std::fs::read_to_string(path)?)?;
+        let base = path.parent().unwrap_or_else(|| Path::new("."));
+
+        let api_keys = config
+            .api_keys
+            .into_iter()
+            .map(|api_key| ApiKey {
+                name: api_key.name,
+                digest: hash(api_key.key.as_bytes()),
+                permissions: api_key.permissions,
+            })
+            .collect();
+
+        let mut jwt_keys = Vec::new();
+        for jwt in config.jwt_keys {
+            let key = match (jwt.algorithm, &jwt.secret, &jwt.public_key) {
+                (Algorithm::HS256, Some(secret), _) => DecodingKey::from_secret(secret.as_bytes()),
+                (Algorithm::ES256, _, Some(public_key)) => {
+                    DecodingKey::from_ec_pem(&std::fs::read(base.join(public_key))?)?
+                }
+                _ => {
+                    return Err(format!(
+                        "JWT key {} needs a secret for HS256 or a public_key for ES256",
+                        jwt.name
+                    )
+                    .into())
+                }
+            };
+            let mut validation = Validation::new(jwt.algorithm);
+            if let Some(issuer) = &jwt.issuer {
+                validation.set_issuer(&[issuer]);
+            }
+            match &jwt.audience {
+                Some(audience) => validation.set_audience(&[audience]),
+                None => validation.validate_aud = false,
+            }
+            jwt_keys.push(JwtKey {
+                name: jwt.name,
+                key,
+                validation,
+                permissions: jwt.permissions,
+            });
+        }
+
+        Ok(Self { api_keys, jwt_keys })
+    }
+
+    fn authenticate(&self, metadata: &MetadataMap) -> Result<Principal, ServiceError> {
+        if let Some(key) = metadata.get(API_KEY_HEADER) {
+            let digest = hash(key.as_bytes());
+            return self
+                .api_keys
+                .iter()
+                .find(|api_key| api_key.digest == digest)
+                .map(|api_key| Principal {
+                    name: api_key.name.clone(),
+                    permissions: api_key.permissions.clone(),
+                })
+                .ok_or_else(|| ServiceError::Unauthenticated("unknown API key".to_string()));
+        }
+
+        let token = metadata
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| {
+                ServiceError::Unauthenticated(format!("missing {} or bearer token", API_KEY_HEADER))
+            })?;
+        let header = decode_header(token)
+            .map_err(|err| ServiceError::Unauthenticated(format!("malformed token: {}", err)))?;
+
+        let mut last_error = None;
+        for jwt in self
+            .jwt_keys
+            .iter()
+            .filter(|jwt| jwt.validation.algorithms.contains(&header.alg))
+            .filter(|jwt| header.kid.iter().all(|kid| *kid == jwt.name))
+        {
+            match decode::<Claims>(token, &jwt.key, &jwt.validation) {
+                Ok(data) => {
+                    let claims = data.claims;
+                    let requested = Permissions {
+                        methods: claims.methods.unwrap_or_else(|| vec!["*".to_string()]),
+                        networks: claims.networks.unwrap_or_else(|| vec!["*".to_string()]),
+                    };
+                    return Ok(Principal {
+                        name: claims.sub.unwrap_or_else(|| jwt.name.clone()),
+                        permissions: jwt.permissions.intersect(&requested),
+                    });
+                }
+                Err(err) => last_error = Some(err),
+            }
+        }
+
+        Err(ServiceError::Unauthenticated(match last_error {
+            Some(err) => format!("invalid token: {}", err),
+            None => "no key configured for the token".to_string(),
+        }))
+    }
+}
+
+/// Credential store backing the auth interceptor. The credentials file is re-read
+/// whenever its modification time changes, so keys can be rotated without a restart.
+pub struct Authenticator {
+    path: PathBuf,
+    credentials: RwLock<Credentials>,
+    modified: Mutex<Option<SystemTime>>,
+}
+
+impl Authenticator {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = path.as_ref().to_path_buf();
+        let modified = std::fs::metadata(&path)?.modified().ok();
+        let credentials = Credentials::load(&path)?;
+
+        Ok(Self {
+            path,
+            credentials: RwLock::new(credentials),
+            modified: Mutex::new(modified),
+        })
+    }
+
+    pub fn authenticate(&self, metadata: &MetadataMap) -> Result<Principal, ServiceError> {
+        self.credentials.read().unwrap().authenticate(metadata)
+    }
+
+    /// Re-reads the credentials file. A broken file keeps the previous credentials.
+    pub fn reload(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let modified = std::fs::metadata(&self.path)?.modified().ok();
+        let credentials = Credentials::load(&self.path)?;
+        *self.credentials.write().unwrap() = credentials;
+        *self.modified.lock().unwrap() = modified;
+        Ok(())
+    }
+
+    fn changed(&self) -> bool {
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        modified.is_some() && modified != *self.modified.lock().unwrap()
+    }
+
+    /// Polls the credentials file and reloads it when it changes.
+    pub fn watch(self: Arc<Self>, period: Duration) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                if !self.changed() {
+                    continue;
+                }
+                match self.reload() {
+                    Ok(()) => info!(path = %self.path.display(), "reloaded credentials"),
+                    Err(err) => error!(
+                        path = %self.path.display(),
+                        error = %err,
+                        "failed to reload credentials, keeping the previous ones"
+                    ),
+                }
+            }
+        });
+    }
+}
+
+/// Builds the tonic interceptor. With no authenticator configured every call is let
+/// through; otherwise the caller must present a valid API key or JWT.
+#[allow(clippy::result_large_err)] // the signature is fixed by tonic
+pub fn interceptor(
+    authenticator: Option<Arc<Authenticator>>,
+) -> impl FnMut(Request<()>) -> Result<Request<()>, Status> + Clone {
+    move |mut request: Request<()>| {
+        if let Some(authenticator) = &authenticator {
+            let principal = authenticator.authenticate(request.metadata())?;
+            request.extensions_mut().insert(principal);
+        }
+        Ok(request)
+    }
+}
+
+/// Checks that the caller may invoke `method`, and use `network` when the method
+/// talks to a cluster.
+pub fn authorize<T>(
+    request: &Request<T>,
+    required: bool,
+    method: &'static str,
+    network: Option<&str>,
+) -> Result<(), ServiceError> {
+    let principal = match request.extensions().get::<Principal>() {
+        Some(principal) => principal,
+        None if required => {
+            return Err(ServiceError::Unauthenticated(
+                "missing credentials".to_string(),
+            ))
+        }
+        None => return Ok(()),
+    };
+    Span::current().record("principal", principal.name.as_str());
+
+    if !principal.permissions.allows_method(method) {
+        return Err(ServiceError::PermissionDenied {
+            principal: principal.name.clone(),
+            reason: format!("method {} is not allowed", method),
+        });
+    }
+    if let Some(network) = network {
+        if !principal.permissions.allows_network(network) {
+            return Err(ServiceError::PermissionDenied {
+                principal: principal.name.clone(),
+                reason: format!("network {} is not allowed", network),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde::Serialize;
+
+    const CREDENTIALS: &str = r#"
+        [[api_keys]]
+        name = "dashboard"
+        key = "read-only-key"
+        methods = ["GetBalance", "ListNetworks"]
+        networks = ["devnet"]
+
+        [[jwt_keys]]
+        name = "backend"
+        algorithm = "HS256"
+        secret = "jwt-secret"
+        issuer = "sandpit"
+        methods = ["*"]
+        networks = ["devnet", "testnet"]
+    "#;
+
+    #[derive(Serialize)]
+    struct TokenClaims {
+        sub: String,
+        iss: String,
+        exp: u64,
+        methods: Vec<String>,
+    }
+
+    fn authenticator(contents: &str) -> (Arc<Authenticator>, PathBuf) {
+        let path = std::env::temp_dir().join(format!("sandpit-auth-{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(&path, contents).unwrap();
+        (Arc::new(Authenticator::load(&path).unwrap()), path)
+    }
+
+    fn request_with(key: &'static str, value: &str) -> Request<()> {
+        let mut request = Request::new(());
+        request.metadata_mut().insert(key, value.parse().unwrap());
+        request
+    }
+
+    fn authenticate(authenticator: &Arc<Authenticator>, request: Request<()>) -> Request<()> {
+        interceptor(Some(authenticator.clone()))(request).unwrap()
+    }
+
+    #[test]
+    fn test_api_key_permissions() {
+        let (authenticator, path) = authenticator(CREDENTIALS);
+
+        let request = authenticate(
+            &authenticator,
+            request_with(API_KEY_HEADER, "read-only-key"),
+        );
+        assert!(authorize(&request, true, "GetBalance", Some("devnet")).is_ok());
+        assert!(authorize(&request, true, "GetBalance", Some("mainnet")).is_err());
+        assert!(authorize(&request, true, "SendSol", Some("devnet")).is_err());
+
+        assert!(authenticator
+            .authenticate(request_with(API_KEY_HEADER, "wrong").metadata())
+            .is_err());
+        assert!(authenticator
+            .authenticate(Request::new(()).metadata())
+            .is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_jwt_permissions() {
+        let (authenticator, path) = authenticator(CREDENTIALS);
+        let claims = TokenClaims {
+            sub: "payments".to_string(),
+            iss: "sandpit".to_string(),
+            exp: jsonwebtoken::get_current_timestamp() + 60,
+            methods: vec!["SendSol".to_string()],
+        };
+        let token = encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(b"jwt-secret"),
+        )
+        .unwrap();
+
+        let request = authenticate(
+            &authenticator,
+            request_with("authorization", &format!("Bearer {}", token)),
+        );
+        assert!(authorize(&request, true, "SendSol", Some("testnet")).is_ok());
+        assert!(authorize(&request, true, "SendSol", Some("mainnet")).is_err());
+        assert!(authorize(&request, true, "RequestAirdrop", Some("devnet")).is_err());
+
+        let forged = encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(b"other-secret"),
+        )
+        .unwrap();
+        assert!(authenticator
+            .authenticate(request_with("authorization", &format!("Bearer {}", forged)).metadata())
+            .is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_reload() {
+        let (authenticator, path) = authenticator(CREDENTIALS);
+        assert!(authenticator
+            .authenticate(request_with(API_KEY_HEADER, "rotated-key").metadata())
+            .is_err());
+
+        std::fs::write(&path, CREDENTIALS.replace("read-only-key", "rotated-key")).unwrap();
+        authenticator.reload().unwrap();
+        assert!(authenticator
+            .authenticate(request_with(API_KEY_HEADER, "rotated-key").metadata())
+            .is_ok());
+
+        // A broken file keeps the previous credentials
+        std::fs::write(&path, "api_keys = 1").unwrap();
+        assert!(authenticator.reload().is_err());
+        assert!(authenticator
+            .authenticate(request_with(API_KEY_HEADER, "rotated-key").metadata())
+            .is_ok());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,235 @@
+use crate::error::ServiceError;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcAccountInfoConfig;
+use solana_sdk::{clock::Slot, commitment_config::CommitmentConfig, pubkey::Pubkey};
+
+/// Addresses accepted by one `GetBalances` call.
+pub const MAX_BALANCE_ADDRESSES: usize = 10_000;
+
+/// Accounts `getMultipleAccounts` returns per request.
+const MAX_ACCOUNTS_PER_REQUEST: usize = 100;
+
+/// `getMultipleAccounts` requests in flight at once.
+const MAX_PARALLEL_REQUESTS: usize = 8;
+
+/// Rounds of requests made before giving up on reading every chunk at one slot.
+const MAX_SLOT_ATTEMPTS: usize = 5;
+
+/// Lamports held by each of `addresses`, 0 for accounts that do not exist, and the
+/// slot they were all read at. Only balances are fetched, not account data.
+///
+/// Chunks read in parallel may be answered at different slots, so the chunks
+/// behind the newest one are read again with it as their minimum context slot
+/// until every chunk was read at the same slot.
+pub async fn balances(
+    client: &RpcClient,
+    addresses: &[Pubkey],
+    commitment: CommitmentConfig,
+) -> Result<(Vec<u64>, Slot), ServiceError> {
+    let chunks: Vec<_> = addresses.chunks(MAX_ACCOUNTS_PER_REQUEST).collect();
+    let mut stale: Vec<_> = (0..chunks.len()).collect();
+    let mut read = vec![(0, Vec::new()); chunks.len()];
+    let mut min_context_slot = None;
+    for _ in 0..MAX_SLOT_ATTEMPTS {
+        let requests: Vec<_> = stale
+            .iter()
+            .map(|&index| read_chunk(client, chunks[index], commitment, min_context_slot))
+            .collect();
+        let responses: Vec<_> = stream::iter(requests)
+            .buffered(MAX_PARALLEL_REQUESTS)
+            .try_collect()
+            .await?;
+        for (index, response) in stale.into_iter().zip(responses) {
+            read[index] = response;
+        }
+
+        let slot = read.iter().map(|(slot, _)| *slot).max().unwrap_or_default();
+        stale = (0..read.len())
+            .filter(|&index| read[index].0 != slot)
+            .collect();
+        if stale.is_empty() {
+            let lamports = read
+                .into_iter()
+                .flat_map(|(_, lamports)| lamports)
+                .collect();
+            return Ok((lamports, slot));
+        }
+        min_context_slot = Some(slot);
+    }
+    Err(ServiceError::InconsistentSlots)
+}
+
+/// Lamports held by one chunk of accounts and the slot they were read at.
+async fn read_chunk(
+    client: &RpcClient,
+    chunk: &[Pubkey],
+    commitment: CommitmentConfig,
+    min_context_slot: Option<Slot>,
+) -> Result<(Slot, Vec<u64>), ServiceError> {
+    let config = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        data_slice: Some(UiDataSliceConfig {
+            offset: 0,
+            length: 0,
+        }),
+        commitment: Some(commitment),
+        min_context_slot,
+    };
+    let response = client
+        .get_multiple_accounts_with_config(chunk, config)
+        .await?;
+    if response.value.len() != chunk.len() {
+        return Err(ServiceError::Internal(format!(
+            "requested {} accounts, received {}",
+            chunk.len(),
+            response.value.len()
+        )));
+    }
+    let lamports = response
+        .value
+        .into_iter()
+        .map(|account| account.map_or(0, |account| account.lamports))
+        .collect();
+    Ok((response.context.slot, lamports))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::{json, Value};
+    use solana_client::rpc_client::RpcClientConfig;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_client::rpc_sender::{RpcSender, RpcTransportStats};
+    use solana_sdk::system_program;
+    use std::collections::VecDeque;
+    use std::str::FromStr;
+    use std::sync::{Arc, Mutex};
+
+    /// Addresses and minimum context slot of every request sent.
+    type Requests = Arc<Mutex<Vec<(usize, Option<Slot>)>>>;
+
+    /// Answers `getMultipleAccounts` with one account per address, holding the
+    /// lamports written in the first bytes of the address. Requests are answered at
+    /// the scripted slots, then at the minimum context slot they ask for.
+    struct ChunkSender {
+        slots: Mutex<VecDeque<Slot>>,
+        requests: Requests,
+    }
+
+    #[tonic::async_trait]
+    impl RpcSender for ChunkSender {
+        async fn send(
+            &self,
+            request: RpcRequest,
+            params: Value,
+        ) -> solana_client::client_error::Result<Value> {
+            assert_eq!(request, RpcRequest::GetMultipleAccounts);
+            let addresses = params[0].as_array().unwrap();
+            let min_context_slot = params[1]["minContextSlot"].as_u64();
+            self.requests
+                .lock()
+                .unwrap()
+                .push((addresses.len(), min_context_slot));
+            let slot = self.slots.lock().unwrap().pop_front();
+            let accounts: Vec<_> = addresses
+                .iter()
+                .map(|address| {
+                    let address = Pubkey::from_str(address.as_str().unwrap()).unwrap();
+                    json!({
+                        "lamports": lamports(&address),
+                        "data": ["", "base64"],
+                        "owner": system_program::id().to_string(),
+                        "executable": false,
+                        "rentEpoch": 0,
+                    })
+                })
+                .collect();
+            Ok(json!({
+                "context": { "slot": slot.or(min_context_slot).unwrap_or(1) },
+                "value": accounts,
+            }))
+        }
+
+        fn get_transport_stats(&self) -> RpcTransportStats {
+            RpcTransportStats::default()
+        }
+
+        fn url(&self) -> String {
+            "chunks".to_string()
+        }
+    }
+
+    fn lamports(address: &Pubkey) -> u64 {
+        u64::from_le_bytes(address.to_bytes()[..8].try_into().unwrap())
+    }
+
+    fn chunk_client(slots: &[Slot]) -> (RpcClient, Requests) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let sender = ChunkSender {
+            slots: Mutex::new(slots.iter().copied().collect()),
+            requests: requests.clone(),
+        };
+        let client = RpcClient::new_sender(sender, RpcClientConfig::default());
+        (client, requests)
+    }
+
+    #[tokio::test]
+    async fn test_balances() {
+        // The mock answers every getMultipleAccounts with two missing accounts
+        let client = RpcClient::new_mock("succeeds".to_string());
+        let addresses = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let (lamports, slot) = balances(&client, &addresses, client.commitment())
+            .await
+            .unwrap();
+        assert_eq!(lamports, vec![0, 0]);
+        assert_eq!(slot, 1);
+
+        assert!(balances(&client, &[], client.commitment())
+            .await
+            .unwrap()
+            .0
+            .is_empty());
+        assert!(matches!(
+            balances(&client, &addresses[..1], client.commitment()).await,
+            Err(ServiceError::Internal(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_balances_at_one_slot() {
+        let addresses: Vec<_> = (1..=250u64)
+            .map(|lamports| {
+                let mut bytes = [0; 32];
+                bytes[..8].copy_from_slice(&lamports.to_le_bytes());
+                Pubkey::new_from_array(bytes)
+            })
+            .collect();
+
+        // The second chunk is answered at a later slot, so the others are read again
+        let (client, requests) = chunk_client(&[10, 12, 11]);
+        let (lamports, slot) = balances(&client, &addresses, client.commitment())
+            .await
+            .unwrap();
+        assert_eq!(lamports, (1..=250).collect::<Vec<_>>());
+        assert_eq!(slot, 12);
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec![
+                (100, None),
+                (100, None),
+                (50, None),
+                (100, Some(12)),
+                (50, Some(12)),
+            ]
+        );
+
+        // Nodes that keep answering at newer slots make the call fail
+        let (client, _) = chunk_client(&(1..=20).collect::<Vec<_>>());
+        assert!(matches!(
+            balances(&client, &addresses, client.commitment()).await,
+            Err(ServiceError::InconsistentSlots)
+        ));
+    }
+}
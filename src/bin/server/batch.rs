@@ -0,0 +1,106 @@
+use crate::versioned::{serialized_size, v0_message};
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount, hash::Hash, instruction::Instruction,
+    packet::PACKET_DATA_SIZE, pubkey::Pubkey,
+};
+use std::ops::Range;
+
+/// Transactions of a batch submitted at the same time.
+pub const MAX_PARALLEL_SUBMISSIONS: usize = 8;
+
+/// Recipients accepted by one `SendSolBatch` call.
+pub const MAX_BATCH_PAYOUTS: usize = 1000;
+
+/// Splits `payouts`, each given as the instructions making it, into consecutive runs
+/// that each fit a single v0 transaction paid by `payer`, together with the `prefix`
+/// instructions every transaction starts with. Accounts found in `lookup_tables` take
+/// one byte each instead of 32, so more payouts fit.
+pub fn pack(
+    payer: &Pubkey,
+    prefix: &[Instruction],
+    payouts: &[Vec<Instruction>],
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Vec<Range<usize>> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut instructions = prefix.to_vec();
+    for (index, payout) in payouts.iter().enumerate() {
+        instructions.extend_from_slice(payout);
+        // A payout that does not fit starts the next transaction
+        if index > start && !fits(payer, &instructions, lookup_tables) {
+            chunks.push(start..index);
+            start = index;
+            instructions.truncate(prefix.len());
+            instructions.extend_from_slice(payout);
+        }
+    }
+    if start < payouts.len() {
+        chunks.push(start..payouts.len());
+    }
+    chunks
+}
+
+/// Whether the instructions fit a transaction once signed.
+fn fits(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+) -> bool {
+    v0_message(payer, instructions, lookup_tables, Hash::default())
+        .is_ok_and(|message| serialized_size(&message) <= PACKET_DATA_SIZE)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_sdk::{compute_budget::ComputeBudgetInstruction, system_instruction};
+
+    #[test]
+    fn test_pack() {
+        let payer = Pubkey::new_unique();
+        let recipients: Vec<_> = (0..100).map(|_| Pubkey::new_unique()).collect();
+        let payouts: Vec<_> = recipients
+            .iter()
+            .zip(0..)
+            .map(|(to, amount)| {
+                let transfer = system_instruction::transfer(&payer, to, amount);
+                // Some payouts carry a memo, making them larger
+                if amount % 3 == 0 {
+                    vec![spl_memo::build_memo(b"invoice", &[&payer]), transfer]
+                } else {
+                    vec![transfer]
+                }
+            })
+            .collect();
+        let prefix = [ComputeBudgetInstruction::set_compute_unit_price(1)];
+
+        let chunks = pack(&payer, &prefix, &payouts, &[]);
+        assert!(chunks.len() > 1);
+        // Chunks cover every payout once, in order
+        assert_eq!(chunks.first().unwrap().start, 0);
+        assert_eq!(chunks.last().unwrap().end, payouts.len());
+        assert!(chunks.windows(2).all(|pair| pair[0].end == pair[1].start));
+
+        for chunk in &chunks {
+            let mut instructions = prefix.to_vec();
+            instructions.extend(payouts[chunk.clone()].concat());
+            assert!(fits(&payer, &instructions, &[]));
+        }
+        // Each chunk is as full as it can be
+        let first = chunks[0].clone();
+        let mut instructions = prefix.to_vec();
+        instructions.extend(payouts[first.start..=first.end].concat());
+        assert!(!fits(&payer, &instructions, &[]));
+
+        // Recipients in a lookup table need fewer transactions
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: recipients,
+        };
+        let looked_up = pack(&payer, &prefix, &payouts, &[table]);
+        assert!(looked_up.len() < chunks.len());
+        assert_eq!(looked_up.last().unwrap().end, payouts.len());
+
+        assert!(pack(&payer, &[], &[], &[]).is_empty());
+    }
+}
@@ -0,0 +1,210 @@
+use crate::config::ClusterConfig;
+use crate::error::ServiceError;
+use crate::solana::{EpochInfo, GetClusterInfoResponse};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::collections::BTreeMap;
+
+/// A named Solana cluster the service is allowed to talk to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cluster {
+    pub name: String,
+    pub rpc_url: String,
+    pub ws_url: String,
+    /// Whether `RequestAirdrop` may be forwarded to this cluster's faucet.
+    pub faucet: bool,
+}
+
+/// Registry of configured clusters, keyed by the `network` name used in requests.
+#[derive(Debug, Clone)]
+pub struct ClusterRegistry {
+    clusters: BTreeMap<String, Cluster>,
+}
+
+impl ClusterRegistry {
+    pub fn from_config(
+        config: &BTreeMap<String, ClusterConfig>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if config.is_empty() {
+            return Err("configuration does not define any clusters".into());
+        }
+
+        let clusters = config
+            .iter()
+            .map(|(name, cluster)| {
+                let ws_url = cluster
+                    .ws_url
+                    .clone()
+                    .unwrap_or_else(|| websocket_url(&cluster.rpc_url));
+                let cluster = Cluster {
+                    name: name.clone(),
+                    rpc_url: cluster.rpc_url.clone(),
+                    ws_url,
+                    faucet: cluster.faucet,
+                };
+                (name.clone(), cluster)
+            })
+            .collect();
+
+        Ok(Self { clusters })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Cluster> {
+        self.clusters.get(name)
+    }
+
+    /// Resolves the `network` field of a request to a configured cluster.
+    pub fn resolve(&self, name: &str) -> Result<&Cluster, ServiceError> {
+        self.get(name).ok_or_else(|| ServiceError::UnknownNetwork {
+            network: name.to_string(),
+            configured: self.names().join(", "),
+        })
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.clusters.keys().map(String::as_str).collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Cluster> {
+        self.clusters.values()
+    }
+}
+
+/// Describes `cluster` as its RPC node sees it. An unhealthy node is reported as
+/// such rather than failing the call, since it usually still answers the rest.
+pub async fn cluster_info(
+    cluster: &Cluster,
+    client: &RpcClient,
+    commitment: CommitmentConfig,
+) -> Result<GetClusterInfoResponse, ServiceError> {
+    let (version, epoch_info, health) = tokio::join!(
+        client.get_version(),
+        client.get_epoch_info_with_commitment(commitment),
+        client.get_health(),
+    );
+    let version = version?;
+    // Slot and block height come from the epoch info so all three agree
+    let epoch_info = epoch_info?;
+    Ok(GetClusterInfoResponse {
+        network: cluster.name.clone(),
+        rpc_url: cluster.rpc_url.clone(),
+        version: version.solana_core,
+        feature_set: version.feature_set.unwrap_or_default(),
+        slot: epoch_info.absolute_slot,
+        block_height: epoch_info.block_height,
+        epoch_info: Some(EpochInfo {
+            epoch: epoch_info.epoch,
+            slot_index: epoch_info.slot_index,
+            slots_in_epoch: epoch_info.slots_in_epoch,
+        }),
+        healthy: health.is_ok(),
+        health_error: health.err().map(|err| err.to_string()).unwrap_or_default(),
+    })
+}
+
+/// Derives the websocket endpoint from an RPC URL the same way the Solana CLI does:
+/// swap the scheme and, for explicit ports, use the next port up.
+fn websocket_url(rpc_url: &str) -> String {
+    let (scheme, rest) = match rpc_url.split_once("://") {
+        Some(("https", rest)) => ("wss", rest),
+        Some((_, rest)) => ("ws", rest),
+        None => ("ws", rpc_url),
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, ""),
+    };
+    let authority = match authority.rsplit_once(':') {
+        Some((host, port)) => match port.parse::<u16>() {
+            Ok(port) => format!("{}:{}", host, port.saturating_add(1)),
+            Err(_) => authority.to_string(),
+        },
+        None => authority.to_string(),
+    };
+    format!("{}://{}{}", scheme, authority, path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::Config;
+
+    fn from_toml(contents: &str) -> Result<ClusterRegistry, Box<dyn std::error::Error>> {
+        ClusterRegistry::from_config(&Config::from_toml(contents)?.clusters)
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let registry = from_toml(
+            r#"
+            [clusters.localnet]
+            rpc_url = "http://127.0.0.1:8899"
+            faucet = true
+
+            [clusters.private]
+            rpc_url = "https://rpc.example.com/token"
+            ws_url = "wss://ws.example.com/token"
+            "#,
+        )
+        .unwrap();
+
+        let localnet = registry.get("localnet").unwrap();
+        assert_eq!(localnet.ws_url, "ws://127.0.0.1:8900");
+        assert!(localnet.faucet);
+
+        let private = registry.get("private").unwrap();
+        assert_eq!(private.ws_url, "wss://ws.example.com/token");
+        assert!(!private.faucet);
+
+        assert!(registry.resolve("devnet").is_err());
+        assert_eq!(registry.names(), vec!["localnet", "private"]);
+    }
+
+    #[test]
+    fn test_empty_config() {
+        assert!(from_toml("").is_err());
+        assert!(ClusterRegistry::from_config(&Config::default().clusters).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_cluster_info() {
+        let registry = from_toml(
+            r#"
+            [clusters.localnet]
+            rpc_url = "http://127.0.0.1:8899"
+            "#,
+        )
+        .unwrap();
+        let cluster = registry.get("localnet").unwrap();
+        let client = RpcClient::new_mock("succeeds".to_string());
+
+        let info = cluster_info(cluster, &client, client.commitment())
+            .await
+            .unwrap();
+        assert_eq!(info.network, "localnet");
+        assert_eq!(info.rpc_url, "http://127.0.0.1:8899");
+        assert!(!info.version.is_empty());
+        assert_eq!((info.slot, info.block_height), (34, 34));
+        let epoch_info = info.epoch_info.unwrap();
+        assert_eq!(
+            (
+                epoch_info.epoch,
+                epoch_info.slot_index,
+                epoch_info.slots_in_epoch
+            ),
+            (1, 2, 32)
+        );
+    }
+
+    #[test]
+    fn test_websocket_url() {
+        assert_eq!(
+            websocket_url("https://api.devnet.solana.com"),
+            "wss://api.devnet.solana.com"
+        );
+        assert_eq!(
+            websocket_url("http://localhost:8899/"),
+            "ws://localhost:8900/"
+        );
+    }
+}
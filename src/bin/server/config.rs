@@ -0,0 +1,227 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+/// Default location of the server configuration, overridable with `SANDPIT_CONFIG`.
+pub const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+/// Server configuration, read once at startup from `config.toml`.
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub server: ServerConfig,
+    #[serde(default)]
+    pub clusters: BTreeMap<String, ClusterConfig>,
+    #[serde(default)]
+    pub keystore: KeystoreConfig,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    #[serde(default)]
+    pub tracing: TracingConfig,
+    #[serde(default)]
+    pub shutdown: ShutdownConfig,
+    #[serde(default)]
+    pub fees: FeeConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServerConfig {
+    /// Address the gRPC server binds to, overridable with `SANDPIT_LISTEN`.
+    #[serde(default = "default_listen")]
+    pub listen: SocketAddr,
+    /// Serves plaintext gRPC when absent.
+    pub tls: Option<TlsConfig>,
+    /// Address of the HTTP server exporting Prometheus metrics on `/metrics`.
+    /// Metrics are not exported when absent.
+    pub metrics_listen: Option<SocketAddr>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TlsConfig {
+    /// PEM encoded server certificate chain.
+    pub cert: PathBuf,
+    /// PEM encoded private key of the server certificate.
+    pub key: PathBuf,
+    /// PEM encoded CA bundle used to verify client certificates. Setting it enables mTLS.
+    pub client_ca: Option<PathBuf>,
+    /// Accept clients without a certificate even when `client_ca` is set.
+    #[serde(default)]
+    pub client_auth_optional: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClusterConfig {
+    pub rpc_url: String,
+    pub ws_url: Option<String>,
+    #[serde(default)]
+    pub faucet: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeystoreConfig {
+    /// Directory holding one encrypted file per stored wallet.
+    #[serde(default = "default_keystore_path")]
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuthConfig {
+    /// TOML file with API keys and JWT verification keys. Every call is allowed
+    /// when absent. The file is reloaded when it changes.
+    pub credentials: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TracingConfig {
+    #[serde(default)]
+    pub format: LogFormat,
+    /// `EnvFilter` directives, overridden by `RUST_LOG`.
+    #[serde(default = "default_tracing_filter")]
+    pub filter: String,
+    /// OTLP/gRPC collector endpoint, e.g. `http://localhost:4317`. Spans are only
+    /// written to stdout when absent.
+    pub otlp_endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Json,
+    Text,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShutdownConfig {
+    /// How long in-flight transactions may keep running after SIGTERM/SIGINT.
+    #[serde(default = "default_drain_timeout_secs")]
+    pub drain_timeout_secs: u64,
+    /// Where signatures still unconfirmed at shutdown are written, to be
+    /// reconciled on the next start.
+    #[serde(default = "default_recovery_file")]
+    pub recovery_file: PathBuf,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeeConfig {
+    /// Percentile of recent prioritization fees used as the unit price in auto mode.
+    #[serde(default = "default_priority_fee_percentile")]
+    pub priority_fee_percentile: u8,
+}
+
+impl Config {
+    /// Loads the configuration from a TOML file, falling back to the defaults when
+    /// the file does not exist.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = std::fs::read_to_string(path)?;
+        Self::from_toml(&contents)
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(toml::from_str(contents)?)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let clusters = [
+            ("devnet", "https://api.devnet.solana.com", true),
+            ("testnet", "https://api.testnet.solana.com", true),
+            ("mainnet", "https://api.mainnet-beta.solana.com", false),
+        ]
+        .into_iter()
+        .map(|(name, rpc_url, faucet)| {
+            let cluster = ClusterConfig {
+                rpc_url: rpc_url.to_string(),
+                ws_url: None,
+                faucet,
+            };
+            (name.to_string(), cluster)
+        })
+        .collect();
+
+        Self {
+            server: ServerConfig::default(),
+            clusters,
+            keystore: KeystoreConfig::default(),
+            auth: AuthConfig::default(),
+            tracing: TracingConfig::default(),
+            shutdown: ShutdownConfig::default(),
+            fees: FeeConfig::default(),
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            listen: default_listen(),
+            tls: None,
+            metrics_listen: None,
+        }
+    }
+}
+
+impl Default for KeystoreConfig {
+    fn default() -> Self {
+        Self {
+            path: default_keystore_path(),
+        }
+    }
+}
+
+impl Default for TracingConfig {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::default(),
+            filter: default_tracing_filter(),
+            otlp_endpoint: None,
+        }
+    }
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            drain_timeout_secs: default_drain_timeout_secs(),
+            recovery_file: default_recovery_file(),
+        }
+    }
+}
+
+impl Default for FeeConfig {
+    fn default() -> Self {
+        Self {
+            priority_fee_percentile: default_priority_fee_percentile(),
+        }
+    }
+}
+
+fn default_listen() -> SocketAddr {
+    "[::1]:50051".parse().unwrap()
+}
+
+fn default_keystore_path() -> PathBuf {
+    PathBuf::from("keystore")
+}
+
+fn default_tracing_filter() -> String {
+    "info".to_string()
+}
+
+fn default_drain_timeout_secs() -> u64 {
+    30
+}
+
+fn default_recovery_file() -> PathBuf {
+    PathBuf::from("pending-transactions.json")
+}
+
+fn default_priority_fee_percentile() -> u8 {
+    75
+}
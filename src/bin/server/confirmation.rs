@@ -0,0 +1,226 @@
+use crate::error::ServiceError;
+use crate::landed::landed;
+use crate::nonce::can_land;
+use crate::solana::{Commitment, TransactionResult};
+use crate::tracker::REBROADCAST_INTERVAL;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSendTransactionConfig;
+use solana_sdk::{
+    commitment_config::CommitmentConfig, signature::Signature, transaction::VersionedTransaction,
+};
+use solana_transaction_status::{TransactionConfirmationStatus, TransactionStatus};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// How often signature statuses are polled while waiting for confirmation.
+const STATUS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long `confirm` waits for the requested commitment. Blockhashes expire well
+/// before, so this bounds durable nonce transactions that were dropped, whose nonce
+/// keeps them valid, and clusters that stopped making progress.
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Resolves the commitment requested by a call, falling back to the client's own.
+pub fn commitment_config(commitment: Commitment, client: &RpcClient) -> CommitmentConfig {
+    match commitment {
+        Commitment::Unspecified => client.commitment(),
+        Commitment::Processed => CommitmentConfig::processed(),
+        Commitment::Confirmed => CommitmentConfig::confirmed(),
+        Commitment::Finalized => CommitmentConfig::finalized(),
+    }
+}
+
+/// Submits a transaction with preflight at `commitment`, so a transaction depending
+/// on one confirmed just before sees its effects.
+pub async fn send(
+    client: &RpcClient,
+    transaction: &VersionedTransaction,
+    commitment: CommitmentConfig,
+) -> Result<Signature, ServiceError> {
+    let config = RpcSendTransactionConfig {
+        preflight_commitment: Some(commitment.commitment),
+        ..RpcSendTransactionConfig::default()
+    };
+    client
+        .send_transaction_with_config(transaction, config)
+        .await
+        .map_err(|err| ServiceError::transaction(err, &transaction.message))
+}
+
+/// Sends a transaction again without preflight, which already ran on the first
+/// submission, for nodes that dropped it.
+pub async fn rebroadcast(
+    client: &RpcClient,
+    transaction: &VersionedTransaction,
+) -> Result<Signature, ServiceError> {
+    let config = RpcSendTransactionConfig {
+        skip_preflight: true,
+        max_retries: Some(0),
+        ..RpcSendTransactionConfig::default()
+    };
+    Ok(client
+        .send_transaction_with_config(transaction, config)
+        .await?)
+}
+
+/// Polls the status of a submitted transaction until it reaches `commitment`,
+/// re-broadcasting it while the cluster has not seen it. Gives up with
+/// `ConfirmationTimeout` after `CONFIRM_TIMEOUT`, when the transaction may still land.
+///
+/// A transaction that lands but fails is returned with its error set rather than as
+/// an `Err`, since it was still charged.
+pub async fn confirm(
+    client: &RpcClient,
+    transaction: &VersionedTransaction,
+    commitment: CommitmentConfig,
+) -> Result<TransactionStatus, ServiceError> {
+    let signature = transaction.signatures[0];
+    let start = Instant::now();
+    let mut sent_at = start;
+    loop {
+        match signature_status(client, &signature).await? {
+            Some(status) if status.satisfies_commitment(commitment) => return Ok(status),
+            Some(status) => debug!(slot = status.slot, "waiting for commitment"),
+            None => {
+                if !can_land(client, transaction).await? {
+                    return Err(ServiceError::BlockhashExpired);
+                }
+                if sent_at.elapsed() >= REBROADCAST_INTERVAL {
+                    if let Err(err) = rebroadcast(client, transaction).await {
+                        debug!(error = %err, "failed to re-broadcast transaction");
+                    }
+                    sent_at = Instant::now();
+                }
+            }
+        }
+        if start.elapsed() >= CONFIRM_TIMEOUT {
+            return Err(ServiceError::ConfirmationTimeout(signature.to_string()));
+        }
+        tokio::time::sleep(STATUS_POLL_INTERVAL).await;
+    }
+}
+
+/// Status of a single signature, `None` while the cluster has not seen it.
+pub async fn signature_status(
+    client: &RpcClient,
+    signature: &Signature,
+) -> Result<Option<TransactionStatus>, ServiceError> {
+    Ok(client
+        .get_signature_statuses(&[*signature])
+        .await?
+        .value
+        .into_iter()
+        .next()
+        .flatten())
+}
+
+/// Describes a confirmed transaction, with the fee and block time recorded by the
+/// cluster. While the cluster cannot return the transaction yet, which is common at
+/// `processed`, the block time is left at 0 and `quoted_fee` is reported instead.
+pub async fn transaction_result(
+    client: &RpcClient,
+    transaction: &VersionedTransaction,
+    status: &TransactionStatus,
+    quoted_fee: u64,
+) -> TransactionResult {
+    let signature = &transaction.signatures[0];
+    let landed = match landed(client, signature, CommitmentConfig::confirmed()).await {
+        Ok(landed) => landed,
+        Err(err) => {
+            debug!(%signature, error = %err, "landed transaction not available");
+            None
+        }
+    };
+    let (block_time, fee) = match landed {
+        Some(landed) => (landed.block_time.unwrap_or_default(), landed.meta.fee),
+        None => (0, quoted_fee),
+    };
+    let confirmation_status = match &status.confirmation_status {
+        Some(TransactionConfirmationStatus::Processed) => Commitment::Processed,
+        Some(TransactionConfirmationStatus::Confirmed) => Commitment::Confirmed,
+        Some(TransactionConfirmationStatus::Finalized) => Commitment::Finalized,
+        // Older nodes only report the number of confirmations, none meaning rooted
+        None if status.confirmations.is_none() => Commitment::Finalized,
+        None => Commitment::Confirmed,
+    };
+
+    TransactionResult {
+        signature: signature.to_string(),
+        slot: status.slot,
+        block_time,
+        fee,
+        confirmation_status: confirmation_status.into(),
+        error: status
+            .err
+            .as_ref()
+            .map(|err| err.to_string())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::landed::test::encoded;
+    use serde_json::{json, Value};
+    use solana_client::rpc_request::RpcRequest;
+    use solana_rpc_client::mock_sender::Mocks;
+    use solana_sdk::{
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        system_instruction,
+    };
+
+    #[test]
+    fn test_commitment_config() {
+        let client = RpcClient::new_mock("succeeds".to_string());
+        assert_eq!(
+            commitment_config(Commitment::Unspecified, &client),
+            client.commitment()
+        );
+        assert_eq!(
+            commitment_config(Commitment::Processed, &client),
+            CommitmentConfig::processed()
+        );
+        assert_eq!(
+            commitment_config(Commitment::Finalized, &client),
+            CommitmentConfig::finalized()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_transaction_result() {
+        let payer = Keypair::new();
+        let transfer = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+        let landed = encoded(
+            &payer,
+            &[transfer],
+            &[],
+            vec![10_000, 0, 1],
+            vec![4999, 1, 1],
+        );
+        let transaction = landed.transaction.transaction.decode().unwrap();
+        let status = TransactionStatus {
+            slot: 42,
+            confirmations: None,
+            status: Ok(()),
+            err: None,
+            confirmation_status: Some(TransactionConfirmationStatus::Finalized),
+        };
+
+        // The fee recorded by the cluster wins over the quote
+        let mut mocks = Mocks::new();
+        mocks.insert(RpcRequest::GetTransaction, json!(landed));
+        let client = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+        let result = transaction_result(&client, &transaction, &status, 7000).await;
+        assert_eq!(result.fee, 5000);
+        assert_eq!(result.block_time, 1_700_000_000);
+        assert_eq!(result.confirmation_status(), Commitment::Finalized);
+
+        let mut mocks = Mocks::new();
+        mocks.insert(RpcRequest::GetTransaction, Value::Null);
+        let client = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+        let result = transaction_result(&client, &transaction, &status, 7000).await;
+        assert_eq!((result.fee, result.block_time), (7000, 0));
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{GreetingAccount, GREETING_PROGRAM_ID};
+use borsh::BorshDeserialize;
+use serde_json::{json, Value};
+use solana_sdk::{
+    nonce::state::{State as NonceState, Versions as NonceVersions},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_program,
+};
+use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+use std::collections::HashMap;
+
+/// Account data decoded into JSON.
+pub struct Decoded {
+    /// Layout the data was decoded as, such as `spl-token/mint`.
+    pub layout: &'static str,
+    pub value: Value,
+}
+
+/// Decodes the data of accounts owned by one program.
+pub trait AccountDecoder: Send + Sync {
+    /// `Ok(None)` for accounts the program owns but that have no layout worth
+    /// decoding, such as plain system accounts. `Err` when the data does not match
+    /// any layout the decoder knows.
+    fn decode(&self, data: &[u8]) -> Result<Option<Decoded>, String>;
+}
+
+/// Account decoders keyed by the program owning the account.
+pub struct DecoderRegistry {
+    decoders: HashMap<Pubkey, Box<dyn AccountDecoder>>,
+}
+
+impl DecoderRegistry {
+    /// Registry with the decoders this server knows about: greeting accounts, SPL
+    /// Token mints and accounts, and system nonce accounts.
+    pub fn new() -> Self {
+        Self {
+            decoders: HashMap::new(),
+        }
+        .with(GREETING_PROGRAM_ID, GreetingDecoder)
+        .with(spl_token::id(), TokenDecoder)
+        .with(system_program::id(), NonceDecoder)
+    }
+
+    /// Registers `decoder` for accounts owned by `owner`, replacing any previous one.
+    pub fn with(mut self, owner: Pubkey, decoder: impl AccountDecoder + 'static) -> Self {
+        self.decoders.insert(owner, Box::new(decoder));
+        self
+    }
+
+    /// Decodes the data of an account owned by `owner`, `Ok(None)` when no decoder
+    /// is registered for it.
+    pub fn decode(&self, owner: &Pubkey, data: &[u8]) -> Result<Option<Decoded>, String> {
+        match self.decoders.get(owner) {
+            Some(decoder) => decoder.decode(data),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Counter of the greeting program in `program/`.
+struct GreetingDecoder;
+
+impl AccountDecoder for GreetingDecoder {
+    fn decode(&self, data: &[u8]) -> Result<Option<Decoded>, String> {
+        let greeting = GreetingAccount::try_from_slice(data).map_err(|err| err.to_string())?;
+        Ok(Some(Decoded {
+            layout: "greeting",
+            value: json!({ "counter": greeting.counter }),
+        }))
+    }
+}
+
+/// SPL Token mints and token accounts, told apart by their size. Amounts are strings
+/// since they may not fit a JSON number.
+struct TokenDecoder;
+
+impl AccountDecoder for TokenDecoder {
+    fn decode(&self, data: &[u8]) -> Result<Option<Decoded>, String> {
+        let optional = |key: Option<Pubkey>| key.map(|key| key.to_string());
+        match data.len() {
+            Mint::LEN => {
+                let mint = Mint::unpack(data).map_err(|err| err.to_string())?;
+                Ok(Some(Decoded {
+                    layout: "spl-token/mint",
+                    value: json!({
+                        "mint_authority": optional(mint.mint_authority.into()),
+                        "supply": mint.supply.to_string(),
+                        "decimals": mint.decimals,
+                        "is_initialized": mint.is_initialized,
+                        "freeze_authority": optional(mint.freeze_authority.into()),
+                    }),
+                }))
+            }
+            TokenAccount::LEN => {
+                let account = TokenAccount::unpack(data).map_err(|err| err.to_string())?;
+                let state = match account.state {
+                    AccountState::Uninitialized => "uninitialized",
+                    AccountState::Initialized => "initialized",
+                    AccountState::Frozen => "frozen",
+                };
+                Ok(Some(Decoded {
+                    layout: "spl-token/account",
+                    value: json!({
+                        "mint": account.mint.to_string(),
+                        "owner": account.owner.to_string(),
+                        "amount": account.amount.to_string(),
+                        "delegate": optional(account.delegate.into()),
+                        "delegated_amount": account.delegated_amount.to_string(),
+                        "state": state,
+                        "is_native": account.is_native(),
+                        "close_authority": optional(account.close_authority.into()),
+                    }),
+                }))
+            }
+            len => Err(format!(
+                "{} bytes is neither a mint nor a token account",
+                len
+            )),
+        }
+    }
+}
+
+/// Durable nonce accounts. Other system accounts carry no data.
+struct NonceDecoder;
+
+impl AccountDecoder for NonceDecoder {
+    fn decode(&self, data: &[u8]) -> Result<Option<Decoded>, String> {
+        if data.is_empty() {
+            return Ok(None);
+        }
+        let versions: NonceVersions = bincode::deserialize(data).map_err(|err| err.to_string())?;
+        let value = match versions.state() {
+            NonceState::Uninitialized => json!({ "state": "uninitialized" }),
+            NonceState::Initialized(data) => json!({
+                "state": "initialized",
+                "authority": data.authority.to_string(),
+                "nonce": data.blockhash().to_string(),
+                "lamports_per_signature": data.fee_calculator.lamports_per_signature,
+            }),
+        };
+        Ok(Some(Decoded {
+            layout: "nonce",
+            value,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_sdk::program_option::COption;
+
+    #[test]
+    fn test_builtin_decoders() {
+        let registry = DecoderRegistry::new();
+
+        let decoded = registry
+            .decode(&GREETING_PROGRAM_ID, &7u32.to_le_bytes())
+            .unwrap()
+            .unwrap();
+        assert_eq!(decoded.layout, "greeting");
+        assert_eq!(decoded.value, json!({ "counter": 7 }));
+
+        let authority = Pubkey::new_unique();
+        let mut data = vec![0; Mint::LEN];
+        Mint::pack(
+            Mint {
+                mint_authority: COption::Some(authority),
+                supply: u64::MAX,
+                decimals: 6,
+                is_initialized: true,
+                freeze_authority: COption::None,
+            },
+            &mut data,
+        )
+        .unwrap();
+        let decoded = registry.decode(&spl_token::id(), &data).unwrap().unwrap();
+        assert_eq!(decoded.layout, "spl-token/mint");
+        assert_eq!(decoded.value["mint_authority"], authority.to_string());
+        assert_eq!(decoded.value["supply"], u64::MAX.to_string());
+        assert_eq!(decoded.value["freeze_authority"], Value::Null);
+        assert!(registry.decode(&spl_token::id(), &[0; 3]).is_err());
+
+        // Plain system accounts and unknown owners are left alone
+        assert!(registry
+            .decode(&system_program::id(), &[])
+            .unwrap()
+            .is_none());
+        assert!(registry
+            .decode(&Pubkey::new_unique(), &[1, 2, 3])
+            .unwrap()
+            .is_none());
+    }
+}
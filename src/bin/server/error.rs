@@ -0,0 +1,385 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_request::RpcError;
+use solana_sdk::{
+    bs58,
+    instruction::InstructionError,
+    message::VersionedMessage,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    system_program,
+    transaction::{TransactionError, VersionedTransaction},
+};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+use tonic::{Code, Status};
+use tonic_types::{ErrorDetails, StatusExt};
+
+/// `ErrorInfo.domain` attached to every error returned by the service.
+pub const ERROR_DOMAIN: &str = "solana-sandpit";
+
+/// `SystemError::ResultWithNegativeLamports`, returned by the system program when a
+/// transfer or account creation exceeds the payer's balance.
+const SYSTEM_INSUFFICIENT_LAMPORTS: u32 = 1;
+
+/// Failures surfaced by `MySolanaService`, mapped onto gRPC status codes with
+/// `google.rpc` error details so clients can branch on `ErrorInfo.reason`.
+#[derive(Debug, thiserror::Error)]
+pub enum ServiceError {
+    #[error("Invalid network identifier {network}. Configured networks: {configured}.")]
+    UnknownNetwork { network: String, configured: String },
+    #[error("Faucet is not available on {0}.")]
+    FaucetUnavailable(String),
+    #[error("Invalid public key in {field}: {value}")]
+    InvalidPubkey { field: &'static str, value: String },
+    #[error("Invalid secret key in {field}")]
+    InvalidSecretKey { field: &'static str },
+    #[error("Invalid {field}: {reason}")]
+    InvalidArgument { field: &'static str, reason: String },
+    #[error("Insufficient funds: {0}")]
+    InsufficientFunds(TransactionError),
+    #[error("Blockhash expired before the transaction was confirmed")]
+    BlockhashExpired,
+    #[error("Transaction {0} was not confirmed in time, it may still land")]
+    ConfirmationTimeout(String),
+    #[error("Transaction failed: {0}")]
+    Program(TransactionError),
+    #[error("RPC request failed: {0}")]
+    Rpc(#[source] Box<ClientError>),
+    #[error("Wallet {0} not found")]
+    WalletNotFound(String),
+    #[error("Account {0} not found")]
+    AccountNotFound(String),
+    #[error("Transaction {0} not found")]
+    TransactionNotFound(String),
+    #[error("Wallet {0} is locked")]
+    WalletLocked(String),
+    #[error("Invalid passphrase for wallet {0}")]
+    InvalidPassphrase(String),
+    #[error("Unauthenticated: {0}")]
+    Unauthenticated(String),
+    #[error("Permission denied for {principal}: {reason}")]
+    PermissionDenied { principal: String, reason: String },
+    #[error("Invalid nonce account {address}: {reason}")]
+    InvalidNonceAccount { address: String, reason: String },
+    #[error("Invalid address lookup table {address}: {reason}")]
+    InvalidLookupTable { address: String, reason: String },
+    #[error("Accounts could not be read at a single slot")]
+    InconsistentSlots,
+    #[error("Server is shutting down")]
+    ShuttingDown,
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+impl ServiceError {
+    /// Classifies a failed transaction submission. The message is used to tell
+    /// system program balance errors apart from custom errors of other programs.
+    pub fn transaction(err: ClientError, message: &VersionedMessage) -> Self {
+        // Program ids are never loaded from lookup tables, so the static keys suffice
+        let program_id = |index: u8| {
+            let instruction = message.instructions().get(usize::from(index))?;
+            message
+                .static_account_keys()
+                .get(usize::from(instruction.program_id_index))
+        };
+        match err.get_transaction_error() {
+            Some(TransactionError::InstructionError(
+                index,
+                InstructionError::Custom(SYSTEM_INSUFFICIENT_LAMPORTS),
+            )) if program_id(index) == Some(&system_program::id()) => {
+                Self::InsufficientFunds(TransactionError::InstructionError(
+                    index,
+                    InstructionError::Custom(SYSTEM_INSUFFICIENT_LAMPORTS),
+                ))
+            }
+            _ => err.into(),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self {
+            Self::UnknownNetwork { .. } => "UNKNOWN_NETWORK",
+            Self::FaucetUnavailable(_) => "FAUCET_UNAVAILABLE",
+            Self::InvalidPubkey { .. } => "INVALID_PUBKEY",
+            Self::InvalidSecretKey { .. } => "INVALID_SECRET_KEY",
+            Self::InvalidArgument { .. } => "INVALID_ARGUMENT",
+            Self::InsufficientFunds(_) => "INSUFFICIENT_FUNDS",
+            Self::BlockhashExpired => "BLOCKHASH_EXPIRED",
+            Self::ConfirmationTimeout(_) => "CONFIRMATION_TIMEOUT",
+            Self::Program(_) => "PROGRAM_ERROR",
+            Self::Rpc(_) => "RPC_UNAVAILABLE",
+            Self::WalletNotFound(_) => "WALLET_NOT_FOUND",
+            Self::AccountNotFound(_) => "ACCOUNT_NOT_FOUND",
+            Self::TransactionNotFound(_) => "TRANSACTION_NOT_FOUND",
+            Self::WalletLocked(_) => "WALLET_LOCKED",
+            Self::InvalidPassphrase(_) => "INVALID_PASSPHRASE",
+            Self::Unauthenticated(_) => "UNAUTHENTICATED",
+            Self::PermissionDenied { .. } => "PERMISSION_DENIED",
+            Self::InvalidNonceAccount { .. } => "INVALID_NONCE_ACCOUNT",
+            Self::InvalidLookupTable { .. } => "INVALID_LOOKUP_TABLE",
+            Self::InconsistentSlots => "INCONSISTENT_SLOTS",
+            Self::ShuttingDown => "SHUTTING_DOWN",
+            Self::Internal(_) => "INTERNAL",
+        }
+    }
+
+    fn code(&self) -> Code {
+        match self {
+            Self::UnknownNetwork { .. }
+            | Self::InvalidPubkey { .. }
+            | Self::InvalidSecretKey { .. }
+            | Self::InvalidArgument { .. } => Code::InvalidArgument,
+            Self::FaucetUnavailable(_)
+            | Self::InsufficientFunds(_)
+            | Self::Program(_)
+            | Self::WalletLocked(_)
+            | Self::InvalidNonceAccount { .. }
+            | Self::InvalidLookupTable { .. } => Code::FailedPrecondition,
+            Self::BlockhashExpired => Code::Aborted,
+            Self::ConfirmationTimeout(_) => Code::DeadlineExceeded,
+            Self::Rpc(_) | Self::InconsistentSlots | Self::ShuttingDown => Code::Unavailable,
+            Self::WalletNotFound(_) | Self::AccountNotFound(_) | Self::TransactionNotFound(_) => {
+                Code::NotFound
+            }
+            Self::InvalidPassphrase(_) | Self::PermissionDenied { .. } => Code::PermissionDenied,
+            Self::Unauthenticated(_) => Code::Unauthenticated,
+            Self::Internal(_) => Code::Internal,
+        }
+    }
+}
+
+impl From<ClientError> for ServiceError {
+    fn from(err: ClientError) -> Self {
+        match err.get_transaction_error() {
+            Some(
+                err @ (TransactionError::InsufficientFundsForFee
+                | TransactionError::InsufficientFundsForRent { .. }),
+            ) => Self::InsufficientFunds(err),
+            Some(TransactionError::BlockhashNotFound) => Self::BlockhashExpired,
+            Some(err) => Self::Program(err),
+            None => match err.kind() {
+                // `send_and_confirm_transaction` gives up once the blockhash is no longer valid
+                ClientErrorKind::RpcError(RpcError::ForUser(message))
+                    if message.starts_with("unable to confirm transaction") =>
+                {
+                    Self::BlockhashExpired
+                }
+                _ => Self::Rpc(Box::new(err)),
+            },
+        }
+    }
+}
+
+impl From<ServiceError> for Status {
+    fn from(err: ServiceError) -> Self {
+        let mut metadata = HashMap::new();
+        let mut details = ErrorDetails::new();
+
+        match &err {
+            ServiceError::UnknownNetwork { network, .. } => {
+                metadata.insert("network".to_string(), network.clone());
+                details.add_bad_request_violation("network", err.to_string());
+            }
+            ServiceError::FaucetUnavailable(network) => {
+                metadata.insert("network".to_string(), network.clone());
+                details.add_precondition_failure_violation("FAUCET", network, err.to_string());
+            }
+            ServiceError::InvalidPubkey { field, .. }
+            | ServiceError::InvalidSecretKey { field }
+            | ServiceError::InvalidArgument { field, .. } => {
+                metadata.insert("field".to_string(), field.to_string());
+                details.add_bad_request_violation(*field, err.to_string());
+            }
+            ServiceError::InsufficientFunds(tx_err) | ServiceError::Program(tx_err) => {
+                metadata.insert("transaction_error".to_string(), format!("{:?}", tx_err));
+                if let TransactionError::InstructionError(index, _) = tx_err {
+                    metadata.insert("instruction_index".to_string(), index.to_string());
+                }
+            }
+            ServiceError::BlockhashExpired => {
+                details.set_retry_info(Some(Duration::ZERO));
+            }
+            ServiceError::Rpc(client_err) => {
+                if let Some(request) = client_err.request() {
+                    metadata.insert("rpc_method".to_string(), request.to_string());
+                }
+                details.set_retry_info(Some(Duration::from_secs(1)));
+            }
+            ServiceError::WalletNotFound(id) | ServiceError::InvalidPassphrase(id) => {
+                metadata.insert("wallet_id".to_string(), id.clone());
+            }
+            ServiceError::AccountNotFound(address) => {
+                metadata.insert("address".to_string(), address.clone());
+            }
+            ServiceError::TransactionNotFound(signature)
+            | ServiceError::ConfirmationTimeout(signature) => {
+                metadata.insert("signature".to_string(), signature.clone());
+            }
+            ServiceError::WalletLocked(id) => {
+                metadata.insert("wallet_id".to_string(), id.clone());
+                details.add_precondition_failure_violation("WALLET_LOCKED", id, err.to_string());
+            }
+            ServiceError::InvalidNonceAccount { address, .. } => {
+                metadata.insert("nonce_address".to_string(), address.clone());
+                details.add_precondition_failure_violation(
+                    "NONCE_ACCOUNT",
+                    address,
+                    err.to_string(),
+                );
+            }
+            ServiceError::InvalidLookupTable { address, .. } => {
+                metadata.insert("lookup_table_address".to_string(), address.clone());
+                details.add_precondition_failure_violation(
+                    "LOOKUP_TABLE",
+                    address,
+                    err.to_string(),
+                );
+            }
+            ServiceError::PermissionDenied { principal, .. } => {
+                metadata.insert("principal".to_string(), principal.clone());
+            }
+            ServiceError::InconsistentSlots | ServiceError::ShuttingDown => {
+                details.set_retry_info(Some(Duration::from_secs(1)));
+            }
+            ServiceError::Unauthenticated(_) | ServiceError::Internal(_) => {}
+        }
+        details.set_error_info(err.reason(), ERROR_DOMAIN, metadata);
+
+        Status::with_error_details(err.code(), err.to_string(), details)
+    }
+}
+
+/// Parses a base58 public key from the named request field.
+pub fn parse_pubkey(field: &'static str, value: &str) -> Result<Pubkey, ServiceError> {
+    Pubkey::from_str(value).map_err(|_| ServiceError::InvalidPubkey {
+        field,
+        value: value.to_string(),
+    })
+}
+
+/// Parses a base58 transaction signature from the named request field.
+pub fn parse_signature(field: &'static str, value: &str) -> Result<Signature, ServiceError> {
+    Signature::from_str(value).map_err(|_| ServiceError::InvalidArgument {
+        field,
+        reason: format!("not a transaction signature: {}", value),
+    })
+}
+
+/// Parses a base58 encoded 64 byte keypair from the named request field.
+pub fn parse_keypair(field: &'static str, value: &str) -> Result<Keypair, ServiceError> {
+    bs58::decode(value)
+        .into_vec()
+        .ok()
+        .and_then(|bytes| Keypair::from_bytes(&bytes).ok())
+        .ok_or(ServiceError::InvalidSecretKey { field })
+}
+
+/// Parses a base64 encoded wire transaction, legacy or v0, from the named request
+/// field, requiring every signature to be present and valid.
+pub fn parse_transaction(
+    field: &'static str,
+    value: &str,
+) -> Result<VersionedTransaction, ServiceError> {
+    let invalid = |reason: String| ServiceError::InvalidArgument { field, reason };
+    let bytes = BASE64
+        .decode(value)
+        .map_err(|err| invalid(format!("not base64: {}", err)))?;
+    let transaction: VersionedTransaction = bincode::deserialize(&bytes)
+        .map_err(|err| invalid(format!("not a serialized transaction: {}", err)))?;
+    transaction
+        .sanitize()
+        .map_err(|err| invalid(err.to_string()))?;
+
+    let missing: Vec<String> = transaction
+        .signatures
+        .iter()
+        .zip(transaction.message.static_account_keys())
+        .filter(|(signature, _)| **signature == Signature::default())
+        .map(|(_, pubkey)| pubkey.to_string())
+        .collect();
+    if !missing.is_empty() {
+        return Err(invalid(format!(
+            "missing signatures of {}",
+            missing.join(", ")
+        )));
+    }
+    if !transaction.verify_with_results().into_iter().all(|valid| valid) {
+        return Err(invalid("signature verification failed".to_string()));
+    }
+    Ok(transaction)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_sdk::{
+        hash::Hash, message::Message, signer::Signer, system_instruction, transaction::Transaction,
+    };
+
+    #[test]
+    fn test_status_details() {
+        let status = Status::from(parse_pubkey("wallet_address", "not-a-key").unwrap_err());
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        let details = status.get_error_details();
+        let info = details.error_info().unwrap();
+        assert_eq!(info.reason, "INVALID_PUBKEY");
+        assert_eq!(info.domain, ERROR_DOMAIN);
+        assert_eq!(
+            details.bad_request().unwrap().field_violations[0].field,
+            "wallet_address"
+        );
+    }
+
+    #[test]
+    fn test_transaction_error_mapping() {
+        let payer = Pubkey::new_unique();
+        let message = VersionedMessage::Legacy(Message::new(
+            &[system_instruction::transfer(&payer, &Pubkey::new_unique(), 1)],
+            Some(&payer),
+        ));
+
+        let err = ClientError::from(ClientErrorKind::TransactionError(
+            TransactionError::InstructionError(0, InstructionError::Custom(1)),
+        ));
+        assert!(matches!(
+            ServiceError::transaction(err, &message),
+            ServiceError::InsufficientFunds(_)
+        ));
+
+        let err = ClientError::from(ClientErrorKind::TransactionError(
+            TransactionError::BlockhashNotFound,
+        ));
+        let status = Status::from(ServiceError::transaction(err, &message));
+        assert_eq!(status.code(), Code::Aborted);
+        assert!(status.get_error_details().retry_info().is_some());
+    }
+
+    #[test]
+    fn test_parse_transaction() {
+        let payer = Keypair::new();
+        let to = Pubkey::new_unique();
+        let message = Message::new(
+            &[system_instruction::transfer(&payer.pubkey(), &to, 1)],
+            Some(&payer.pubkey()),
+        );
+        let unsigned = Transaction::new_unsigned(message.clone());
+        let encode =
+            |transaction: &Transaction| BASE64.encode(bincode::serialize(transaction).unwrap());
+
+        assert!(matches!(
+            parse_transaction("transaction", &encode(&unsigned)),
+            Err(ServiceError::InvalidArgument { reason, .. }) if reason.contains("missing signatures")
+        ));
+        assert!(parse_transaction("transaction", "not base64!").is_err());
+
+        // Legacy transactions are accepted as they are
+        let signed = Transaction::new(&[&payer], message, Hash::new_unique());
+        assert_eq!(
+            parse_transaction("transaction", &encode(&signed)).unwrap(),
+            VersionedTransaction::from(signed)
+        );
+    }
+}
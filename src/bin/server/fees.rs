@@ -0,0 +1,115 @@
+use crate::config::FeeConfig;
+use crate::error::ServiceError;
+use crate::solana::ComputeBudget;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    compute_budget::{self, ComputeBudgetInstruction},
+    instruction::Instruction,
+    message::VersionedMessage,
+    pubkey::Pubkey,
+};
+
+/// Resolves the requested compute budget into the instructions to prepend to a
+/// transaction. Nothing is prepended when the request leaves the budget unset.
+///
+/// In auto mode the unit price is the configured percentile of the prioritization
+/// fees recently paid for transactions that locked any of `writable` accounts.
+pub async fn compute_budget_instructions(
+    client: &RpcClient,
+    config: &FeeConfig,
+    budget: Option<&ComputeBudget>,
+    writable: &[Pubkey],
+) -> Result<Vec<Instruction>, ServiceError> {
+    let Some(budget) = budget else {
+        return Ok(Vec::new());
+    };
+    if budget.auto_price && budget.unit_price != 0 {
+        return Err(ServiceError::InvalidArgument {
+            field: "compute_budget",
+            reason: "set either unit_price or auto_price, not both".to_string(),
+        });
+    }
+
+    let unit_price = if budget.auto_price {
+        let fees = client.get_recent_prioritization_fees(writable).await?;
+        let mut fees: Vec<u64> = fees.into_iter().map(|fee| fee.prioritization_fee).collect();
+        percentile(&mut fees, config.priority_fee_percentile)
+    } else {
+        budget.unit_price
+    };
+
+    let mut instructions = Vec::new();
+    if budget.unit_limit != 0 {
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(
+            budget.unit_limit,
+        ));
+    }
+    if unit_price != 0 {
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_price(unit_price));
+    }
+    Ok(instructions)
+}
+
+/// Fee the cluster charges for `message`, priority fee included.
+pub async fn message_fee(
+    client: &RpcClient,
+    message: &VersionedMessage,
+) -> Result<u64, ServiceError> {
+    Ok(match message {
+        VersionedMessage::Legacy(message) => client.get_fee_for_message(message).await?,
+        VersionedMessage::V0(message) => client.get_fee_for_message(message).await?,
+    })
+}
+
+/// Fee the cluster charges for `message`, split into the base fee and the priority
+/// fee paid for its compute unit price. The base fee is quoted for the same message
+/// without its compute budget instructions.
+pub async fn quote(
+    client: &RpcClient,
+    message: &VersionedMessage,
+) -> Result<(u64, u64), ServiceError> {
+    let fee = message_fee(client, message).await?;
+    let mut base = message.clone();
+    let instructions = match &mut base {
+        VersionedMessage::Legacy(message) => &mut message.instructions,
+        VersionedMessage::V0(message) => &mut message.instructions,
+    };
+    instructions.retain(|instruction| {
+        message
+            .static_account_keys()
+            .get(usize::from(instruction.program_id_index))
+            != Some(&compute_budget::id())
+    });
+    if instructions.len() == message.instructions().len() {
+        return Ok((fee, 0));
+    }
+    let base_fee = message_fee(client, &base).await?;
+    Ok((base_fee, fee.saturating_sub(base_fee)))
+}
+
+/// Nearest-rank percentile, `0` for no samples.
+fn percentile(samples: &mut [u64], percentile: u8) -> u64 {
+    if samples.is_empty() {
+        return 0;
+    }
+    samples.sort_unstable();
+    let rank = (usize::from(percentile.min(100)) * samples.len()).div_ceil(100);
+    samples[rank.saturating_sub(1)]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        assert_eq!(percentile(&mut [], 75), 0);
+
+        let mut fees = [0, 10, 0, 40, 30, 20, 0, 0, 50, 0];
+        assert_eq!(percentile(&mut fees, 0), 0);
+        assert_eq!(percentile(&mut fees, 50), 0);
+        assert_eq!(percentile(&mut fees, 75), 30);
+        assert_eq!(percentile(&mut fees, 90), 40);
+        assert_eq!(percentile(&mut fees, 100), 50);
+    }
+}
@@ -0,0 +1,167 @@
+use crate::cluster::ClusterRegistry;
+use crate::pool::RpcClientPool;
+use crate::solana::solana_service_server::SolanaServiceServer;
+use crate::MySolanaService;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tonic::server::NamedService;
+use tonic_health::server::HealthReporter;
+use tonic_health::ServingStatus;
+use tracing::{info, warn};
+
+/// How often upstream clusters are probed with `getHealth`.
+pub const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Publishes cluster health on the `grpc.health.v1.Health` service.
+///
+/// Every configured cluster is reported under its network name, so a load balancer
+/// can probe e.g. `devnet` directly. `solana.SolanaService` is serving while at least
+/// one cluster is healthy, and the server itself (`""`) is always serving.
+pub struct HealthMonitor {
+    reporter: HealthReporter,
+    clusters: Arc<ClusterRegistry>,
+    clients: Arc<RpcClientPool>,
+    healthy: HashMap<String, bool>,
+}
+
+impl HealthMonitor {
+    pub fn new(
+        reporter: HealthReporter,
+        clusters: Arc<ClusterRegistry>,
+        clients: Arc<RpcClientPool>,
+    ) -> Self {
+        Self {
+            reporter,
+            clusters,
+            clients,
+            healthy: HashMap::new(),
+        }
+    }
+
+    /// Probes every cluster once and updates the reported statuses.
+    pub async fn check(&mut self) {
+        let mut checks = JoinSet::new();
+        for cluster in self.clusters.iter() {
+            let name = cluster.name.clone();
+            let client = self.clients.get(&name);
+            checks.spawn(async move {
+                let result = match client {
+                    Some(client) => client.get_health().await.map_err(|err| err.to_string()),
+                    None => Err("no RPC client".to_string()),
+                };
+                (name, result)
+            });
+        }
+
+        while let Some(joined) = checks.join_next().await {
+            let Ok((name, result)) = joined else { continue };
+            let healthy = result.is_ok();
+            if self.healthy.get(&name) != Some(&healthy) {
+                match &result {
+                    Ok(()) => info!(cluster = %name, "cluster is healthy"),
+                    Err(err) => warn!(cluster = %name, error = %err, "cluster is unhealthy"),
+                }
+            }
+            self.reporter
+                .set_service_status(&name, serving_status(healthy))
+                .await;
+            self.healthy.insert(name, healthy);
+        }
+
+        let any_healthy = self.healthy.values().any(|healthy| *healthy);
+        self.reporter
+            .set_service_status(
+                <SolanaServiceServer<MySolanaService> as NamedService>::NAME,
+                serving_status(any_healthy),
+            )
+            .await;
+    }
+
+    /// Keeps probing the clusters in the background.
+    pub fn spawn(mut self, period: Duration) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                self.check().await;
+            }
+        });
+    }
+}
+
+fn serving_status(healthy: bool) -> ServingStatus {
+    if healthy {
+        ServingStatus::Serving
+    } else {
+        ServingStatus::NotServing
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::Config;
+    use crate::metrics::Metrics;
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::transport::{Channel, Server};
+    use tonic_health::pb::health_client::HealthClient;
+    use tonic_health::pb::HealthCheckRequest;
+
+    #[tokio::test]
+    async fn test_unreachable_cluster_is_not_serving() {
+        // Nothing listens on port 1, so getHealth fails immediately
+        let config = Config::from_toml(
+            r#"
+            [clusters.localnet]
+            rpc_url = "http://127.0.0.1:1"
+            "#,
+        )
+        .unwrap();
+        let clusters = Arc::new(ClusterRegistry::from_config(&config.clusters).unwrap());
+        let clients = Arc::new(RpcClientPool::new(
+            &clusters,
+            Arc::new(Metrics::new().unwrap()),
+        ));
+
+        let (reporter, health_service) = tonic_health::server::health_reporter();
+        HealthMonitor::new(reporter, clusters, clients)
+            .check()
+            .await;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(
+            Server::builder()
+                .add_service(health_service)
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        let channel = Channel::from_shared(format!("http://127.0.0.1:{}", port))
+            .unwrap()
+            .connect()
+            .await
+            .unwrap();
+        let mut client = HealthClient::new(channel);
+
+        for (service, expected) in [
+            ("", ServingStatus::Serving),
+            ("localnet", ServingStatus::NotServing),
+            ("solana.SolanaService", ServingStatus::NotServing),
+        ] {
+            let response = client
+                .check(HealthCheckRequest {
+                    service: service.to_string(),
+                })
+                .await
+                .unwrap();
+            assert_eq!(
+                response.into_inner().status(),
+                expected.into(),
+                "{}",
+                service
+            );
+        }
+    }
+}
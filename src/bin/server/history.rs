@@ -0,0 +1,212 @@
+use crate::error::ServiceError;
+use crate::landed::{history_commitment, landed, Invoked, Landed};
+use crate::memo::memos;
+use crate::solana::{Commitment, HistoryEntry, SolTransfer};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+use solana_sdk::{
+    commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature,
+    system_instruction::SystemInstruction, system_program,
+};
+use solana_transaction_status::TransactionConfirmationStatus;
+use std::str::FromStr;
+use tracing::debug;
+
+/// Entries returned when a request does not set a limit.
+pub const DEFAULT_HISTORY_LIMIT: usize = 20;
+
+/// Entries returned by one page at most. Each costs a `getTransaction` call.
+pub const MAX_HISTORY_LIMIT: usize = 100;
+
+/// `getTransaction` calls in flight at once while summarizing a page.
+const MAX_PARALLEL_FETCHES: usize = 8;
+
+/// Transactions involving `address`, newest first, starting after `before` and
+/// stopping at `until`.
+pub async fn history(
+    client: &RpcClient,
+    address: &Pubkey,
+    before: Option<Signature>,
+    until: Option<Signature>,
+    limit: usize,
+    commitment: CommitmentConfig,
+) -> Result<Vec<HistoryEntry>, ServiceError> {
+    let commitment = history_commitment(commitment);
+    let config = GetConfirmedSignaturesForAddress2Config {
+        before,
+        until,
+        limit: Some(limit),
+        commitment: Some(commitment),
+    };
+    let statuses = client
+        .get_signatures_for_address_with_config(address, config)
+        .await?;
+
+    let fetches: Vec<_> = statuses
+        .into_iter()
+        .map(|status| async move {
+            let signature = Signature::from_str(&status.signature).map_err(|_| {
+                ServiceError::Internal(format!("invalid signature {}", status.signature))
+            })?;
+            let landed = landed(client, &signature, commitment).await?;
+            if landed.is_none() {
+                // Nodes may prune transactions they still list signatures for
+                debug!(%signature, "transaction not available, skipping summary");
+            }
+            Ok::<_, ServiceError>(entry(address, status, landed.as_ref()))
+        })
+        .collect();
+    stream::iter(fetches)
+        .buffered(MAX_PARALLEL_FETCHES)
+        .try_collect()
+        .await
+}
+
+/// Summarizes a transaction from the point of view of `address`. Only the status
+/// is known when the transaction itself could not be fetched.
+fn entry(
+    address: &Pubkey,
+    status: RpcConfirmedTransactionStatusWithSignature,
+    landed: Option<&Landed>,
+) -> HistoryEntry {
+    let confirmation_status = match status.confirmation_status {
+        Some(TransactionConfirmationStatus::Processed) => Commitment::Processed,
+        Some(TransactionConfirmationStatus::Confirmed) => Commitment::Confirmed,
+        Some(TransactionConfirmationStatus::Finalized) => Commitment::Finalized,
+        None => Commitment::Unspecified,
+    };
+    let mut entry = HistoryEntry {
+        signature: status.signature,
+        slot: status.slot,
+        block_time: status.block_time.unwrap_or_default(),
+        confirmation_status: confirmation_status.into(),
+        error: status.err.map(|err| err.to_string()).unwrap_or_default(),
+        ..HistoryEntry::default()
+    };
+    let Some(landed) = landed else {
+        return entry;
+    };
+
+    let instructions = landed.instructions();
+    entry.fee = landed.meta.fee;
+    entry.balance_change = landed
+        .balances()
+        .find(|(key, _, _)| *key == address)
+        .map_or(0, |(_, pre, post)| post as i64 - pre as i64);
+    entry.transfers = instructions
+        .iter()
+        .filter_map(sol_transfer)
+        .filter(|transfer| transfer.from == *address || transfer.to == *address)
+        .map(|transfer| SolTransfer {
+            from: transfer.from.to_string(),
+            to: transfer.to.to_string(),
+            lamports: transfer.lamports,
+        })
+        .collect();
+    entry.memos = memos(&landed.transaction.message);
+    for invoked in &instructions {
+        let program_id = invoked.program_id.to_string();
+        if !entry.programs.contains(&program_id) {
+            entry.programs.push(program_id);
+        }
+    }
+    entry
+}
+
+struct Transfer {
+    from: Pubkey,
+    to: Pubkey,
+    lamports: u64,
+}
+
+/// Lamports moved by a system program instruction, whether a plain transfer, the
+/// deposit into a new account or a nonce withdrawal.
+fn sol_transfer(invoked: &Invoked) -> Option<Transfer> {
+    if invoked.program_id != system_program::id() {
+        return None;
+    }
+    let (to, lamports) = match bincode::deserialize(&invoked.data).ok()? {
+        SystemInstruction::Transfer { lamports }
+        | SystemInstruction::CreateAccount { lamports, .. }
+        | SystemInstruction::CreateAccountWithSeed { lamports, .. }
+        | SystemInstruction::WithdrawNonceAccount(lamports) => (1, lamports),
+        SystemInstruction::TransferWithSeed { lamports, .. } => (2, lamports),
+        _ => return None,
+    };
+    Some(Transfer {
+        from: *invoked.accounts.first()?,
+        to: *invoked.accounts.get(to)?,
+        lamports,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::landed::test::encoded;
+    use solana_sdk::{
+        instruction::{AccountMeta, Instruction},
+        signature::{Keypair, Signer},
+        system_instruction,
+    };
+
+    #[test]
+    fn test_entry() {
+        let payer = Keypair::new();
+        let address = Pubkey::new_unique();
+        let escrow = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let memo = spl_memo::build_memo(b"rent", &[&payer.pubkey()]);
+        let release = Instruction::new_with_bytes(
+            program_id,
+            &[],
+            vec![
+                AccountMeta::new(escrow, false),
+                AccountMeta::new(address, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        );
+        let landed = Landed::decode(encoded(
+            &payer,
+            &[
+                release,
+                system_instruction::transfer(&payer.pubkey(), &address, 300),
+                memo,
+            ],
+            &[system_instruction::transfer(&escrow, &address, 50)],
+            vec![1000, 0, 50, 1, 1, 1],
+            vec![695, 350, 0, 1, 1, 1],
+        ))
+        .unwrap();
+        let status = RpcConfirmedTransactionStatusWithSignature {
+            signature: landed.transaction.signatures[0].to_string(),
+            slot: 42,
+            err: None,
+            memo: Some("[4] rent".to_string()),
+            block_time: Some(1_700_000_000),
+            confirmation_status: Some(TransactionConfirmationStatus::Finalized),
+        };
+
+        let summary = entry(&address, status.clone(), Some(&landed));
+        assert_eq!(summary.fee, 5000);
+        assert_eq!(summary.memos, vec!["rent".to_string()]);
+        assert_eq!(summary.confirmation_status(), Commitment::Finalized);
+        // Transfers made by other programs count too
+        let lamports: Vec<_> = summary.transfers.iter().map(|t| t.lamports).collect();
+        assert_eq!(lamports, vec![50, 300]);
+        assert_eq!(
+            summary.programs,
+            vec![
+                program_id.to_string(),
+                system_program::id().to_string(),
+                spl_memo::id().to_string()
+            ]
+        );
+
+        let bare = entry(&address, status, None);
+        assert_eq!(bare.slot, 42);
+        assert!(bare.transfers.is_empty());
+    }
+}
@@ -0,0 +1,577 @@
+use crate::landed::{Invoked, Landed};
+use crate::solana::DecodedInstruction;
+use crate::GREETING_PROGRAM_ID;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde_json::{json, Map, Value};
+use solana_sdk::{
+    compute_budget, program_option::COption, pubkey::Pubkey, system_instruction::SystemInstruction,
+    system_program,
+};
+use spl_token::instruction::{AuthorityType, TokenInstruction};
+use std::collections::HashMap;
+
+/// An instruction decoded into JSON.
+pub struct Parsed {
+    /// Instruction name, such as `transfer`.
+    pub name: &'static str,
+    pub value: Value,
+}
+
+/// Decodes the instructions of one program.
+pub trait InstructionParser: Send + Sync {
+    /// Short name of the program, such as `system`.
+    fn program(&self) -> &'static str;
+
+    fn parse(&self, accounts: &[Pubkey], data: &[u8]) -> Result<Parsed, String>;
+}
+
+/// Instruction parsers keyed by program id.
+pub struct ParserRegistry {
+    parsers: HashMap<Pubkey, Box<dyn InstructionParser>>,
+}
+
+impl ParserRegistry {
+    /// Registry with the parsers this server knows about: the system, SPL Token,
+    /// compute budget, SPL Memo and greeting programs.
+    pub fn new() -> Self {
+        Self {
+            parsers: HashMap::new(),
+        }
+        .with(system_program::id(), SystemParser)
+        .with(spl_token::id(), TokenParser)
+        .with(compute_budget::id(), ComputeBudgetParser)
+        .with(spl_memo::id(), MemoParser)
+        .with(spl_memo::v1::id(), MemoParser)
+        .with(GREETING_PROGRAM_ID, GreetingParser)
+    }
+
+    /// Registers `parser` for instructions of `program_id`, replacing any previous one.
+    pub fn with(mut self, program_id: Pubkey, parser: impl InstructionParser + 'static) -> Self {
+        self.parsers.insert(program_id, Box::new(parser));
+        self
+    }
+
+    /// Every instruction of a landed transaction, each top level one carrying the
+    /// instructions it invoked.
+    pub fn decode(&self, landed: &Landed) -> Vec<DecodedInstruction> {
+        let mut decoded: Vec<DecodedInstruction> = Vec::new();
+        let mut top_level = None;
+        for invoked in landed.instructions() {
+            let instruction = self.instruction(&invoked);
+            if invoked.stack_height > 1 {
+                if let (Some(parent), Some(index)) = (decoded.last_mut(), top_level) {
+                    if index == invoked.index {
+                        parent.inner_instructions.push(instruction);
+                    }
+                }
+            } else {
+                top_level = Some(invoked.index);
+                decoded.push(instruction);
+            }
+        }
+        decoded
+    }
+
+    /// Unknown programs are returned with their raw data only.
+    fn instruction(&self, invoked: &Invoked) -> DecodedInstruction {
+        let mut instruction = DecodedInstruction {
+            program_id: invoked.program_id.to_string(),
+            accounts: invoked.accounts.iter().map(Pubkey::to_string).collect(),
+            data: BASE64.encode(&invoked.data),
+            stack_height: invoked.stack_height,
+            ..DecodedInstruction::default()
+        };
+        if let Some(parser) = self.parsers.get(&invoked.program_id) {
+            instruction.program = parser.program().to_string();
+            match parser.parse(&invoked.accounts, &invoked.data) {
+                Ok(parsed) => {
+                    instruction.name = parsed.name.to_string();
+                    instruction.parsed = parsed.value.to_string();
+                }
+                Err(err) => instruction.parse_error = err,
+            }
+        }
+        instruction
+    }
+}
+
+/// `args` with the instruction's accounts added under the names of their `roles`.
+/// Accounts past the named ones, such as multisig signers, are only listed in the
+/// instruction's accounts.
+fn parsed(name: &'static str, accounts: &[Pubkey], roles: &[&str], args: Value) -> Parsed {
+    let mut value = match args {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    };
+    for (role, account) in roles.iter().zip(accounts) {
+        value.insert(role.to_string(), account.to_string().into());
+    }
+    Parsed {
+        name,
+        value: Value::Object(value),
+    }
+}
+
+struct SystemParser;
+
+impl InstructionParser for SystemParser {
+    fn program(&self) -> &'static str {
+        "system"
+    }
+
+    fn parse(&self, accounts: &[Pubkey], data: &[u8]) -> Result<Parsed, String> {
+        let instruction: SystemInstruction =
+            bincode::deserialize(data).map_err(|err| err.to_string())?;
+        Ok(match instruction {
+            SystemInstruction::CreateAccount {
+                lamports,
+                space,
+                owner,
+            } => parsed(
+                "createAccount",
+                accounts,
+                &["source", "new_account"],
+                json!({ "lamports": lamports, "space": space, "owner": owner.to_string() }),
+            ),
+            SystemInstruction::Assign { owner } => parsed(
+                "assign",
+                accounts,
+                &["account"],
+                json!({ "owner": owner.to_string() }),
+            ),
+            SystemInstruction::Transfer { lamports } => parsed(
+                "transfer",
+                accounts,
+                &["source", "destination"],
+                json!({ "lamports": lamports }),
+            ),
+            SystemInstruction::CreateAccountWithSeed {
+                base,
+                seed,
+                lamports,
+                space,
+                owner,
+            } => parsed(
+                "createAccountWithSeed",
+                accounts,
+                &["source", "new_account"],
+                json!({
+                    "base": base.to_string(),
+                    "seed": seed,
+                    "lamports": lamports,
+                    "space": space,
+                    "owner": owner.to_string(),
+                }),
+            ),
+            SystemInstruction::AdvanceNonceAccount => parsed(
+                "advanceNonce",
+                accounts,
+                &[
+                    "nonce_account",
+                    "recent_blockhashes_sysvar",
+                    "nonce_authority",
+                ],
+                json!({}),
+            ),
+            SystemInstruction::WithdrawNonceAccount(lamports) => parsed(
+                "withdrawFromNonce",
+                accounts,
+                &[
+                    "nonce_account",
+                    "destination",
+                    "recent_blockhashes_sysvar",
+                    "rent_sysvar",
+                    "nonce_authority",
+                ],
+                json!({ "lamports": lamports }),
+            ),
+            SystemInstruction::InitializeNonceAccount(authority) => parsed(
+                "initializeNonce",
+                accounts,
+                &["nonce_account", "recent_blockhashes_sysvar", "rent_sysvar"],
+                json!({ "nonce_authority": authority.to_string() }),
+            ),
+            SystemInstruction::AuthorizeNonceAccount(authority) => parsed(
+                "authorizeNonce",
+                accounts,
+                &["nonce_account", "nonce_authority"],
+                json!({ "new_authority": authority.to_string() }),
+            ),
+            SystemInstruction::Allocate { space } => parsed(
+                "allocate",
+                accounts,
+                &["account"],
+                json!({ "space": space }),
+            ),
+            SystemInstruction::AllocateWithSeed {
+                base,
+                seed,
+                space,
+                owner,
+            } => parsed(
+                "allocateWithSeed",
+                accounts,
+                &["account", "base"],
+                json!({
+                    "base": base.to_string(),
+                    "seed": seed,
+                    "space": space,
+                    "owner": owner.to_string(),
+                }),
+            ),
+            SystemInstruction::AssignWithSeed { base, seed, owner } => parsed(
+                "assignWithSeed",
+                accounts,
+                &["account", "base"],
+                json!({ "base": base.to_string(), "seed": seed, "owner": owner.to_string() }),
+            ),
+            SystemInstruction::TransferWithSeed {
+                lamports,
+                from_seed,
+                from_owner,
+            } => parsed(
+                "transferWithSeed",
+                accounts,
+                &["source", "source_base", "destination"],
+                json!({
+                    "lamports": lamports,
+                    "source_seed": from_seed,
+                    "source_owner": from_owner.to_string(),
+                }),
+            ),
+            SystemInstruction::UpgradeNonceAccount => {
+                parsed("upgradeNonce", accounts, &["nonce_account"], json!({}))
+            }
+        })
+    }
+}
+
+/// Token amounts are strings since they may not fit a JSON number.
+struct TokenParser;
+
+impl InstructionParser for TokenParser {
+    fn program(&self) -> &'static str {
+        "spl-token"
+    }
+
+    fn parse(&self, accounts: &[Pubkey], data: &[u8]) -> Result<Parsed, String> {
+        let optional = |key: COption<Pubkey>| Option::from(key).map(|key: Pubkey| key.to_string());
+        let amount = |amount: u64| json!({ "amount": amount.to_string() });
+        let checked = |amount: u64, decimals: u8| json!({ "amount": amount.to_string(), "decimals": decimals });
+        let instruction = TokenInstruction::unpack(data).map_err(|err| err.to_string())?;
+        Ok(match instruction {
+            TokenInstruction::InitializeMint {
+                decimals,
+                mint_authority,
+                freeze_authority,
+            } => parsed(
+                "initializeMint",
+                accounts,
+                &["mint", "rent_sysvar"],
+                json!({
+                    "decimals": decimals,
+                    "mint_authority": mint_authority.to_string(),
+                    "freeze_authority": optional(freeze_authority),
+                }),
+            ),
+            TokenInstruction::InitializeMint2 {
+                decimals,
+                mint_authority,
+                freeze_authority,
+            } => parsed(
+                "initializeMint2",
+                accounts,
+                &["mint"],
+                json!({
+                    "decimals": decimals,
+                    "mint_authority": mint_authority.to_string(),
+                    "freeze_authority": optional(freeze_authority),
+                }),
+            ),
+            TokenInstruction::InitializeAccount => parsed(
+                "initializeAccount",
+                accounts,
+                &["account", "mint", "owner", "rent_sysvar"],
+                json!({}),
+            ),
+            TokenInstruction::InitializeAccount2 { owner } => parsed(
+                "initializeAccount2",
+                accounts,
+                &["account", "mint", "rent_sysvar"],
+                json!({ "owner": owner.to_string() }),
+            ),
+            TokenInstruction::InitializeAccount3 { owner } => parsed(
+                "initializeAccount3",
+                accounts,
+                &["account", "mint"],
+                json!({ "owner": owner.to_string() }),
+            ),
+            TokenInstruction::InitializeMultisig { m } => parsed(
+                "initializeMultisig",
+                accounts,
+                &["multisig"],
+                json!({ "m": m }),
+            ),
+            TokenInstruction::InitializeMultisig2 { m } => parsed(
+                "initializeMultisig2",
+                accounts,
+                &["multisig"],
+                json!({ "m": m }),
+            ),
+            TokenInstruction::Transfer { amount: value } => parsed(
+                "transfer",
+                accounts,
+                &["source", "destination", "authority"],
+                amount(value),
+            ),
+            TokenInstruction::Approve { amount: value } => parsed(
+                "approve",
+                accounts,
+                &["source", "delegate", "owner"],
+                amount(value),
+            ),
+            TokenInstruction::Revoke => parsed("revoke", accounts, &["source", "owner"], json!({})),
+            TokenInstruction::SetAuthority {
+                authority_type,
+                new_authority,
+            } => {
+                let authority_type = match authority_type {
+                    AuthorityType::MintTokens => "mintTokens",
+                    AuthorityType::FreezeAccount => "freezeAccount",
+                    AuthorityType::AccountOwner => "accountOwner",
+                    AuthorityType::CloseAccount => "closeAccount",
+                };
+                parsed(
+                    "setAuthority",
+                    accounts,
+                    &["account", "authority"],
+                    json!({
+                        "authority_type": authority_type,
+                        "new_authority": optional(new_authority),
+                    }),
+                )
+            }
+            TokenInstruction::MintTo { amount: value } => parsed(
+                "mintTo",
+                accounts,
+                &["mint", "account", "authority"],
+                amount(value),
+            ),
+            TokenInstruction::Burn { amount: value } => parsed(
+                "burn",
+                accounts,
+                &["account", "mint", "authority"],
+                amount(value),
+            ),
+            TokenInstruction::CloseAccount => parsed(
+                "closeAccount",
+                accounts,
+                &["account", "destination", "owner"],
+                json!({}),
+            ),
+            TokenInstruction::FreezeAccount => parsed(
+                "freezeAccount",
+                accounts,
+                &["account", "mint", "authority"],
+                json!({}),
+            ),
+            TokenInstruction::ThawAccount => parsed(
+                "thawAccount",
+                accounts,
+                &["account", "mint", "authority"],
+                json!({}),
+            ),
+            TokenInstruction::TransferChecked { amount, decimals } => parsed(
+                "transferChecked",
+                accounts,
+                &["source", "mint", "destination", "authority"],
+                checked(amount, decimals),
+            ),
+            TokenInstruction::ApproveChecked { amount, decimals } => parsed(
+                "approveChecked",
+                accounts,
+                &["source", "mint", "delegate", "owner"],
+                checked(amount, decimals),
+            ),
+            TokenInstruction::MintToChecked { amount, decimals } => parsed(
+                "mintToChecked",
+                accounts,
+                &["mint", "account", "authority"],
+                checked(amount, decimals),
+            ),
+            TokenInstruction::BurnChecked { amount, decimals } => parsed(
+                "burnChecked",
+                accounts,
+                &["account", "mint", "authority"],
+                checked(amount, decimals),
+            ),
+            TokenInstruction::SyncNative => parsed("syncNative", accounts, &["account"], json!({})),
+            TokenInstruction::GetAccountDataSize => {
+                parsed("getAccountDataSize", accounts, &["mint"], json!({}))
+            }
+            TokenInstruction::InitializeImmutableOwner => parsed(
+                "initializeImmutableOwner",
+                accounts,
+                &["account"],
+                json!({}),
+            ),
+            TokenInstruction::AmountToUiAmount { amount: value } => {
+                parsed("amountToUiAmount", accounts, &["mint"], amount(value))
+            }
+            TokenInstruction::UiAmountToAmount { ui_amount } => parsed(
+                "uiAmountToAmount",
+                accounts,
+                &["mint"],
+                json!({ "ui_amount": ui_amount }),
+            ),
+        })
+    }
+}
+
+/// Compute budget instructions are decoded by hand: they are borsh encoded with a
+/// newer borsh than this crate's, and the layout is a tag byte followed by one
+/// little endian integer.
+struct ComputeBudgetParser;
+
+impl InstructionParser for ComputeBudgetParser {
+    fn program(&self) -> &'static str {
+        "compute-budget"
+    }
+
+    fn parse(&self, _accounts: &[Pubkey], data: &[u8]) -> Result<Parsed, String> {
+        let invalid = || format!("invalid compute budget instruction {:?}", data);
+        let (tag, arg) = data.split_first().ok_or_else(invalid)?;
+        let u32_arg = || {
+            arg.try_into()
+                .map(u32::from_le_bytes)
+                .map_err(|_| invalid())
+        };
+        let (name, value) = match tag {
+            1 => ("requestHeapFrame", json!({ "bytes": u32_arg()? })),
+            2 => ("setComputeUnitLimit", json!({ "units": u32_arg()? })),
+            3 => {
+                let price = arg
+                    .try_into()
+                    .map(u64::from_le_bytes)
+                    .map_err(|_| invalid())?;
+                ("setComputeUnitPrice", json!({ "micro_lamports": price }))
+            }
+            4 => (
+                "setLoadedAccountsDataSizeLimit",
+                json!({ "bytes": u32_arg()? }),
+            ),
+            _ => return Err(invalid()),
+        };
+        Ok(Parsed { name, value })
+    }
+}
+
+/// Memos are decoded lossily, since the first version of the memo program did not
+/// check for UTF-8.
+struct MemoParser;
+
+impl InstructionParser for MemoParser {
+    fn program(&self) -> &'static str {
+        "spl-memo"
+    }
+
+    fn parse(&self, accounts: &[Pubkey], data: &[u8]) -> Result<Parsed, String> {
+        Ok(Parsed {
+            name: "memo",
+            value: json!({
+                "memo": String::from_utf8_lossy(data),
+                "signers": accounts.iter().map(Pubkey::to_string).collect::<Vec<_>>(),
+            }),
+        })
+    }
+}
+
+/// The greeting program has a single instruction and ignores its data.
+struct GreetingParser;
+
+impl InstructionParser for GreetingParser {
+    fn program(&self) -> &'static str {
+        "greeting"
+    }
+
+    fn parse(&self, accounts: &[Pubkey], _data: &[u8]) -> Result<Parsed, String> {
+        Ok(parsed("greet", accounts, &["greeted_account"], json!({})))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::landed::test::encoded;
+    use solana_sdk::{
+        compute_budget::ComputeBudgetInstruction,
+        instruction::{AccountMeta, Instruction},
+        signature::{Keypair, Signer},
+        system_instruction,
+    };
+
+    #[test]
+    fn test_decode() {
+        let payer = Keypair::new();
+        let greeted = Pubkey::new_unique();
+        let unknown = Pubkey::new_unique();
+        let greet = Instruction::new_with_bytes(
+            GREETING_PROGRAM_ID,
+            &[],
+            vec![AccountMeta::new(greeted, false)],
+        );
+        let call = Instruction::new_with_bytes(
+            unknown,
+            &[9, 9],
+            vec![
+                AccountMeta::new(greeted, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        );
+        let landed = Landed::decode(encoded(
+            &payer,
+            &[
+                call,
+                ComputeBudgetInstruction::set_compute_unit_price(1_000),
+                greet,
+                spl_memo::build_memo(b"hi", &[&payer.pubkey()]),
+            ],
+            &[system_instruction::transfer(&payer.pubkey(), &greeted, 5)],
+            vec![],
+            vec![],
+        ))
+        .unwrap();
+
+        let decoded = ParserRegistry::new().decode(&landed);
+        let names: Vec<_> = decoded.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, vec!["", "setComputeUnitPrice", "greet", "memo"]);
+
+        // Unknown programs keep their raw data and the instructions they invoked
+        assert!(decoded[0].program.is_empty());
+        assert_eq!(decoded[0].data, BASE64.encode([9, 9]));
+        assert_eq!(decoded[0].inner_instructions.len(), 1);
+        let transfer = &decoded[0].inner_instructions[0];
+        assert_eq!(transfer.name, "transfer");
+        assert_eq!(transfer.stack_height, 2);
+        let parsed: Value = serde_json::from_str(&transfer.parsed).unwrap();
+        assert_eq!(parsed["destination"], greeted.to_string());
+        assert_eq!(parsed["lamports"], 5);
+
+        let parsed: Value = serde_json::from_str(&decoded[1].parsed).unwrap();
+        assert_eq!(parsed["micro_lamports"], 1_000);
+        let parsed: Value = serde_json::from_str(&decoded[2].parsed).unwrap();
+        assert_eq!(parsed["greeted_account"], greeted.to_string());
+
+        let registry = ParserRegistry::new();
+        let garbage = registry.instruction(&Invoked {
+            index: 0,
+            stack_height: 1,
+            program_id: spl_token::id(),
+            accounts: vec![],
+            data: vec![255],
+        });
+        assert_eq!(garbage.program, "spl-token");
+        assert!(garbage.name.is_empty());
+        assert!(!garbage.parse_error.is_empty());
+    }
+}
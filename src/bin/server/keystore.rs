@@ -0,0 +1,303 @@
+use crate::error::ServiceError;
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    bs58,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+const SALT_LEN: usize = 16;
+
+/// Encrypted keypair as persisted in `<keystore>/<wallet_id>.json`.
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredWallet {
+    id: String,
+    public_key: String,
+    created_at: u64,
+    /// Argon2id salt, base58 encoded.
+    salt: String,
+    /// ChaCha20-Poly1305 nonce, base58 encoded.
+    nonce: String,
+    /// Encrypted 64 byte keypair, base58 encoded.
+    ciphertext: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct WalletInfo {
+    pub id: String,
+    pub public_key: String,
+    pub created_at: u64,
+    pub unlocked: bool,
+}
+
+/// Server-side wallet storage. Keypairs are encrypted at rest with a key derived from
+/// a per-wallet passphrase and are only held in memory while the wallet is unlocked.
+///
+/// All methods do blocking file IO and key derivation, so call them from
+/// `spawn_blocking` when running on the async runtime.
+pub struct Keystore {
+    dir: PathBuf,
+    unlocked: RwLock<HashMap<String, Arc<Keypair>>>,
+}
+
+impl Keystore {
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, std::io::Error> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            unlocked: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Generates a new keypair and stores it. The new wallet starts unlocked.
+    pub fn create(&self, passphrase: &str) -> Result<WalletInfo, ServiceError> {
+        self.store(Keypair::new(), passphrase)
+    }
+
+    /// Stores an existing keypair. The imported wallet starts unlocked.
+    pub fn store(&self, keypair: Keypair, passphrase: &str) -> Result<WalletInfo, ServiceError> {
+        if passphrase.is_empty() {
+            return Err(ServiceError::InvalidArgument {
+                field: "passphrase",
+                reason: "must not be empty".to_string(),
+            });
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let public_key = keypair.pubkey().to_string();
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let cipher = cipher(passphrase, &salt)?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &keypair.to_bytes(),
+                    aad: public_key.as_bytes(),
+                },
+            )
+            .map_err(|_| ServiceError::Internal("failed to encrypt keypair".to_string()))?;
+
+        let stored = StoredWallet {
+            id: id.clone(),
+            public_key: public_key.clone(),
+            created_at,
+            salt: bs58::encode(salt).into_string(),
+            nonce: bs58::encode(nonce).into_string(),
+            ciphertext: bs58::encode(ciphertext).into_string(),
+        };
+        let contents = serde_json::to_vec_pretty(&stored)
+            .map_err(|err| ServiceError::Internal(err.to_string()))?;
+        fs::write(self.dir.join(format!("{}.json", id)), contents)
+            .map_err(|err| ServiceError::Internal(err.to_string()))?;
+
+        self.unlocked
+            .write()
+            .unwrap()
+            .insert(id.clone(), Arc::new(keypair));
+
+        Ok(WalletInfo {
+            id,
+            public_key,
+            created_at,
+            unlocked: true,
+        })
+    }
+
+    pub fn list(&self) -> Result<Vec<WalletInfo>, ServiceError> {
+        let entries = fs::read_dir(&self.dir).map_err(|err| ServiceError::Internal(err.to_string()))?;
+        let unlocked = self.unlocked.read().unwrap();
+
+        let mut wallets = Vec::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|err| ServiceError::Internal(err.to_string()))?
+                .path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let stored = read_wallet(&path)?;
+            wallets.push(WalletInfo {
+                unlocked: unlocked.contains_key(&stored.id),
+                id: stored.id,
+                public_key: stored.public_key,
+                created_at: stored.created_at,
+            });
+        }
+        wallets.sort_by_key(|wallet| wallet.created_at);
+
+        Ok(wallets)
+    }
+
+    /// Decrypts the wallet and keeps the keypair in memory for signing.
+    pub fn unlock(&self, id: &str, passphrase: &str) -> Result<WalletInfo, ServiceError> {
+        let stored = read_wallet(&self.path(id)?)?;
+        let keypair = decrypt(&stored, passphrase)?;
+        self.unlocked
+            .write()
+            .unwrap()
+            .insert(stored.id.clone(), Arc::new(keypair));
+
+        Ok(WalletInfo {
+            id: stored.id,
+            public_key: stored.public_key,
+            created_at: stored.created_at,
+            unlocked: true,
+        })
+    }
+
+    /// Drops the decrypted keypair from memory. The wallet stays on disk.
+    pub fn lock(&self, id: &str) -> Result<(), ServiceError> {
+        // Make sure the wallet exists so typos are reported instead of ignored
+        self.path(id).and_then(|path| read_wallet(&path))?;
+        self.unlocked.write().unwrap().remove(id);
+        Ok(())
+    }
+
+    /// Removes the wallet from disk. The passphrase is required to confirm ownership.
+    pub fn delete(&self, id: &str, passphrase: &str) -> Result<(), ServiceError> {
+        let path = self.path(id)?;
+        let stored = read_wallet(&path)?;
+        decrypt(&stored, passphrase)?;
+
+        fs::remove_file(&path).map_err(|err| ServiceError::Internal(err.to_string()))?;
+        self.unlocked.write().unwrap().remove(id);
+        Ok(())
+    }
+
+    /// Returns the keypair of an unlocked wallet for signing.
+    pub fn signer(&self, id: &str) -> Result<Arc<Keypair>, ServiceError> {
+        if let Some(keypair) = self.unlocked.read().unwrap().get(id) {
+            return Ok(keypair.clone());
+        }
+        self.path(id).and_then(|path| read_wallet(&path))?;
+        Err(ServiceError::WalletLocked(id.to_string()))
+    }
+
+    /// Public key of a wallet, which does not need to be unlocked.
+    pub fn public_key(&self, id: &str) -> Result<Pubkey, ServiceError> {
+        if let Some(keypair) = self.unlocked.read().unwrap().get(id) {
+            return Ok(keypair.pubkey());
+        }
+        let stored = self.path(id).and_then(|path| read_wallet(&path))?;
+        Pubkey::from_str(&stored.public_key).map_err(|err| ServiceError::Internal(err.to_string()))
+    }
+
+    /// Maps a wallet id to its file, rejecting anything that is not a UUID so ids
+    /// can never escape the keystore directory.
+    fn path(&self, id: &str) -> Result<PathBuf, ServiceError> {
+        let id = Uuid::parse_str(id).map_err(|_| ServiceError::WalletNotFound(id.to_string()))?;
+        Ok(self.dir.join(format!("{}.json", id)))
+    }
+}
+
+fn read_wallet(path: &Path) -> Result<StoredWallet, ServiceError> {
+    let contents = fs::read(path).map_err(|err| match err.kind() {
+        std::io::ErrorKind::NotFound => ServiceError::WalletNotFound(
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        ),
+        _ => ServiceError::Internal(err.to_string()),
+    })?;
+    serde_json::from_slice(&contents).map_err(|err| ServiceError::Internal(err.to_string()))
+}
+
+fn decrypt(stored: &StoredWallet, passphrase: &str) -> Result<Keypair, ServiceError> {
+    let decode = |value: &str| {
+        bs58::decode(value)
+            .into_vec()
+            .map_err(|err| ServiceError::Internal(format!("corrupt wallet {}: {}", stored.id, err)))
+    };
+    let salt = decode(&stored.salt)?;
+    let nonce = decode(&stored.nonce)?;
+    let ciphertext = decode(&stored.ciphertext)?;
+    let nonce = <[u8; 12]>::try_from(nonce.as_slice())
+        .map_err(|_| ServiceError::Internal(format!("corrupt wallet {}", stored.id)))?;
+
+    let bytes = cipher(passphrase, &salt)?
+        .decrypt(
+            &Nonce::from(nonce),
+            Payload {
+                msg: &ciphertext,
+                aad: stored.public_key.as_bytes(),
+            },
+        )
+        .map_err(|_| ServiceError::InvalidPassphrase(stored.id.clone()))?;
+    Keypair::from_bytes(&bytes)
+        .map_err(|err| ServiceError::Internal(format!("corrupt wallet {}: {}", stored.id, err)))
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<ChaCha20Poly1305, ServiceError> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|err| ServiceError::Internal(err.to_string()))?;
+    Ok(ChaCha20Poly1305::new(&Key::from(key)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_keystore_lifecycle() {
+        let dir = std::env::temp_dir().join(format!("sandpit-keystore-{}", Uuid::new_v4()));
+        let keystore = Keystore::open(&dir).unwrap();
+
+        let wallet = keystore.create("hunter2").unwrap();
+        assert!(keystore.signer(&wallet.id).is_ok());
+
+        keystore.lock(&wallet.id).unwrap();
+        assert!(matches!(
+            keystore.signer(&wallet.id),
+            Err(ServiceError::WalletLocked(_))
+        ));
+        assert_eq!(
+            keystore.public_key(&wallet.id).unwrap().to_string(),
+            wallet.public_key
+        );
+        assert!(matches!(
+            keystore.unlock(&wallet.id, "wrong"),
+            Err(ServiceError::InvalidPassphrase(_))
+        ));
+
+        keystore.unlock(&wallet.id, "hunter2").unwrap();
+        let signer = keystore.signer(&wallet.id).unwrap();
+        assert_eq!(signer.pubkey().to_string(), wallet.public_key);
+
+        let listed = keystore.list().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert!(listed[0].unlocked);
+
+        keystore.delete(&wallet.id, "hunter2").unwrap();
+        assert!(matches!(
+            keystore.signer(&wallet.id),
+            Err(ServiceError::WalletNotFound(_))
+        ));
+        assert!(matches!(
+            keystore.signer("../config"),
+            Err(ServiceError::WalletNotFound(_))
+        ));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,293 @@
+use crate::error::ServiceError;
+use serde_json::json;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_client::rpc_request::RpcRequest;
+use solana_sdk::{
+    bs58,
+    clock::{Slot, UnixTimestamp},
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, UiInnerInstructions, UiInstruction,
+    UiLoadedAddresses, UiTransactionEncoding, UiTransactionStatusMeta,
+};
+use std::str::FromStr;
+
+/// A transaction that landed, as recorded by the cluster.
+pub struct Landed {
+    pub slot: Slot,
+    pub block_time: Option<UnixTimestamp>,
+    pub transaction: VersionedTransaction,
+    pub meta: UiTransactionStatusMeta,
+    /// Static keys followed by the writable then readonly keys loaded from lookup
+    /// tables, the order instruction account indexes refer to.
+    pub account_keys: Vec<Pubkey>,
+}
+
+/// An instruction executed by a transaction, top level or invoked by another program.
+pub struct Invoked {
+    /// Index of the top level instruction this one belongs to.
+    pub index: usize,
+    /// 1 for top level instructions, one more for each level of cross-program
+    /// invocation. Nodes that do not record it report inner instructions as 2.
+    pub stack_height: u32,
+    pub program_id: Pubkey,
+    pub accounts: Vec<Pubkey>,
+    pub data: Vec<u8>,
+}
+
+/// History is only kept for confirmed blocks, so `processed` is raised to `confirmed`.
+pub fn history_commitment(commitment: CommitmentConfig) -> CommitmentConfig {
+    if commitment.is_at_least_confirmed() {
+        commitment
+    } else {
+        CommitmentConfig::confirmed()
+    }
+}
+
+/// Fetches a landed transaction, `None` when the cluster has no record of it.
+pub async fn landed(
+    client: &RpcClient,
+    signature: &Signature,
+    commitment: CommitmentConfig,
+) -> Result<Option<Landed>, ServiceError> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(history_commitment(commitment)),
+        max_supported_transaction_version: Some(0),
+    };
+    // `get_transaction_with_config` fails to deserialize the null returned for
+    // unknown signatures, which would be reported as the RPC node being unavailable
+    let encoded: Option<EncodedConfirmedTransactionWithStatusMeta> = client
+        .send(
+            RpcRequest::GetTransaction,
+            json!([signature.to_string(), config]),
+        )
+        .await?;
+    encoded.map(Landed::decode).transpose()
+}
+
+impl Landed {
+    pub fn decode(
+        encoded: EncodedConfirmedTransactionWithStatusMeta,
+    ) -> Result<Self, ServiceError> {
+        let invalid =
+            |reason: &str| ServiceError::Internal(format!("invalid transaction: {}", reason));
+        let transaction = encoded
+            .transaction
+            .transaction
+            .decode()
+            .ok_or_else(|| invalid("undecodable transaction"))?;
+        let meta = encoded
+            .transaction
+            .meta
+            .ok_or_else(|| invalid("missing status meta"))?;
+
+        let mut account_keys = transaction.message.static_account_keys().to_vec();
+        let loaded: Option<&UiLoadedAddresses> = meta.loaded_addresses.as_ref().into();
+        if let Some(loaded) = loaded {
+            for address in loaded.writable.iter().chain(&loaded.readonly) {
+                account_keys.push(
+                    Pubkey::from_str(address).map_err(|_| invalid("invalid loaded address"))?,
+                );
+            }
+        }
+
+        Ok(Self {
+            slot: encoded.slot,
+            block_time: encoded.block_time,
+            transaction,
+            meta,
+            account_keys,
+        })
+    }
+
+    /// Every instruction executed, each top level one followed by the instructions
+    /// it invoked.
+    pub fn instructions(&self) -> Vec<Invoked> {
+        let inner: Option<&Vec<UiInnerInstructions>> = self.meta.inner_instructions.as_ref().into();
+        let mut invoked = Vec::new();
+        for (index, instruction) in self.transaction.message.instructions().iter().enumerate() {
+            invoked.extend(self.invoked(
+                index,
+                1,
+                instruction.program_id_index,
+                &instruction.accounts,
+                instruction.data.clone(),
+            ));
+            let inner_instructions = inner
+                .into_iter()
+                .flatten()
+                .filter(|inner| usize::from(inner.index) == index)
+                .flat_map(|inner| &inner.instructions);
+            for instruction in inner_instructions {
+                // Base64 encoded transactions always carry compiled inner instructions
+                let UiInstruction::Compiled(instruction) = instruction else {
+                    continue;
+                };
+                let Ok(data) = bs58::decode(&instruction.data).into_vec() else {
+                    continue;
+                };
+                invoked.extend(self.invoked(
+                    index,
+                    instruction.stack_height.unwrap_or(2),
+                    instruction.program_id_index,
+                    &instruction.accounts,
+                    data,
+                ));
+            }
+        }
+        invoked
+    }
+
+    fn invoked(
+        &self,
+        index: usize,
+        stack_height: u32,
+        program_id_index: u8,
+        accounts: &[u8],
+        data: Vec<u8>,
+    ) -> Option<Invoked> {
+        let key = |index: u8| self.account_keys.get(usize::from(index)).copied();
+        Some(Invoked {
+            index,
+            stack_height,
+            program_id: key(program_id_index)?,
+            accounts: accounts
+                .iter()
+                .map(|&index| key(index))
+                .collect::<Option<_>>()?,
+            data,
+        })
+    }
+
+    /// Lamports held by each account before and after the transaction, fee included.
+    pub fn balances(&self) -> impl Iterator<Item = (&Pubkey, u64, u64)> {
+        self.account_keys
+            .iter()
+            .zip(&self.meta.pre_balances)
+            .zip(&self.meta.post_balances)
+            .map(|((address, &pre), &post)| (address, pre, post))
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+    use solana_sdk::{
+        hash::Hash,
+        instruction::{AccountMeta, CompiledInstruction, Instruction},
+        message::{v0, VersionedMessage},
+        signature::{Keypair, Signer},
+        system_instruction, system_program,
+        transaction::TransactionVersion,
+    };
+    use solana_transaction_status::{
+        EncodedTransaction, EncodedTransactionWithStatusMeta, InnerInstruction, InnerInstructions,
+        TransactionBinaryEncoding, TransactionStatusMeta,
+    };
+
+    /// A transaction paid by `payer` running `instructions`, where the first one
+    /// invoked `inner`, as `getTransaction` would return it.
+    pub fn encoded(
+        payer: &Keypair,
+        instructions: &[Instruction],
+        inner: &[Instruction],
+        pre_balances: Vec<u64>,
+        post_balances: Vec<u64>,
+    ) -> EncodedConfirmedTransactionWithStatusMeta {
+        let message =
+            v0::Message::try_compile(&payer.pubkey(), instructions, &[], Hash::new_unique())
+                .unwrap();
+        let index = |key: &Pubkey| {
+            message
+                .account_keys
+                .iter()
+                .position(|candidate| candidate == key)
+                .unwrap() as u8
+        };
+        let inner: Vec<_> = inner
+            .iter()
+            .map(|instruction| InnerInstruction {
+                instruction: CompiledInstruction {
+                    program_id_index: index(&instruction.program_id),
+                    accounts: instruction
+                        .accounts
+                        .iter()
+                        .map(|meta| index(&meta.pubkey))
+                        .collect(),
+                    data: instruction.data.clone(),
+                },
+                stack_height: Some(2),
+            })
+            .collect();
+        let transaction =
+            VersionedTransaction::try_new(VersionedMessage::V0(message), &[payer]).unwrap();
+        let meta = TransactionStatusMeta {
+            fee: 5000,
+            pre_balances,
+            post_balances,
+            inner_instructions: Some(vec![InnerInstructions {
+                index: 0,
+                instructions: inner,
+            }]),
+            log_messages: Some(vec!["Program log: hello".to_string()]),
+            ..TransactionStatusMeta::default()
+        };
+        EncodedConfirmedTransactionWithStatusMeta {
+            slot: 42,
+            block_time: Some(1_700_000_000),
+            transaction: EncodedTransactionWithStatusMeta {
+                transaction: EncodedTransaction::Binary(
+                    BASE64.encode(bincode::serialize(&transaction).unwrap()),
+                    TransactionBinaryEncoding::Base64,
+                ),
+                meta: Some(meta.into()),
+                version: Some(TransactionVersion::Number(0)),
+            },
+        }
+    }
+
+    #[test]
+    fn test_instructions() {
+        let payer = Keypair::new();
+        let program_id = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let call = Instruction::new_with_bytes(
+            program_id,
+            &[1],
+            vec![
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new(recipient, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        );
+        let cpi = system_instruction::transfer(&payer.pubkey(), &recipient, 7);
+        let landed = Landed::decode(encoded(
+            &payer,
+            &[call],
+            &[cpi],
+            vec![100, 0, 1, 1],
+            vec![88, 7, 1, 1],
+        ))
+        .unwrap();
+
+        let invoked = landed.instructions();
+        assert_eq!(invoked.len(), 2);
+        assert_eq!(invoked[0].program_id, program_id);
+        assert_eq!(invoked[0].stack_height, 1);
+        assert_eq!(invoked[1].program_id, system_program::id());
+        assert_eq!(invoked[1].accounts, vec![payer.pubkey(), recipient]);
+        assert_eq!((invoked[1].index, invoked[1].stack_height), (0, 2));
+
+        // Writable accounts come first, the payer leading
+        let balances: Vec<_> = landed.balances().collect();
+        assert_eq!(balances[0], (&payer.pubkey(), 100, 88));
+        assert_eq!(balances[1], (&recipient, 0, 7));
+    }
+}
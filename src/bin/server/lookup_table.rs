@@ -0,0 +1,159 @@
+use crate::error::ServiceError;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    account::Account,
+    address_lookup_table::{
+        self,
+        state::{AddressLookupTable, LookupTableMeta, LookupTableStatus},
+        AddressLookupTableAccount,
+    },
+    clock::Slot,
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    slot_hashes::SlotHashes,
+    sysvar,
+};
+
+/// Addresses added by one extend instruction, which keeps the transaction well under
+/// the packet size limit.
+pub const MAX_EXTEND_ADDRESSES: usize = 20;
+
+/// An address lookup table as stored on chain.
+pub struct LookupTable {
+    pub address: Pubkey,
+    pub meta: LookupTableMeta,
+    pub addresses: Vec<Pubkey>,
+}
+
+impl LookupTable {
+    /// Whether the table has been deactivated, though it may still be cooling down.
+    pub fn is_deactivated(&self) -> bool {
+        self.meta.deactivation_slot != Slot::MAX
+    }
+
+    /// Whether the table can be deactivated, extended or closed by `authority`.
+    /// Frozen tables have no authority and can never be changed again.
+    pub fn check_authority(&self, authority: &Pubkey) -> Result<(), ServiceError> {
+        match self.meta.authority {
+            Some(current) if current == *authority => Ok(()),
+            Some(current) => {
+                Err(self.invalid(format!("authority is {}, not {}", current, authority)))
+            }
+            None => Err(self.invalid("table is frozen".to_string())),
+        }
+    }
+
+    /// The table as used to compile v0 messages. Deactivated tables are rejected,
+    /// since transactions loading from them may not land.
+    pub fn account(self) -> Result<AddressLookupTableAccount, ServiceError> {
+        if self.is_deactivated() {
+            return Err(self.invalid("table is deactivated".to_string()));
+        }
+        Ok(AddressLookupTableAccount {
+            key: self.address,
+            addresses: self.addresses,
+        })
+    }
+
+    pub fn invalid(&self, reason: String) -> ServiceError {
+        ServiceError::InvalidLookupTable {
+            address: self.address.to_string(),
+            reason,
+        }
+    }
+}
+
+/// Reads the lookup table stored at `address`.
+pub async fn lookup_table(
+    client: &RpcClient,
+    address: &Pubkey,
+    commitment: CommitmentConfig,
+) -> Result<LookupTable, ServiceError> {
+    let account = client
+        .get_account_with_commitment(address, commitment)
+        .await?
+        .value
+        .ok_or_else(|| ServiceError::InvalidLookupTable {
+            address: address.to_string(),
+            reason: "account not found".to_string(),
+        })?;
+    decode(address, &account)
+}
+
+fn decode(address: &Pubkey, account: &Account) -> Result<LookupTable, ServiceError> {
+    let invalid = |reason: String| ServiceError::InvalidLookupTable {
+        address: address.to_string(),
+        reason,
+    };
+    if account.owner != address_lookup_table::program::id() {
+        return Err(invalid(format!(
+            "owned by {}, not the address lookup table program",
+            account.owner
+        )));
+    }
+    let table =
+        AddressLookupTable::deserialize(&account.data).map_err(|err| invalid(err.to_string()))?;
+    Ok(LookupTable {
+        address: *address,
+        meta: table.meta,
+        addresses: table.addresses.into_owned(),
+    })
+}
+
+/// Where a table is in its lifecycle. A deactivated table cools down for about 512
+/// slots, the length of the slot hashes history, before it can be closed.
+pub async fn status(
+    client: &RpcClient,
+    meta: &LookupTableMeta,
+) -> Result<LookupTableStatus, ServiceError> {
+    if meta.deactivation_slot == Slot::MAX {
+        return Ok(LookupTableStatus::Activated);
+    }
+    let slot = client.get_slot().await?;
+    let account = client.get_account(&sysvar::slot_hashes::id()).await?;
+    let slot_hashes: SlotHashes = bincode::deserialize(&account.data)
+        .map_err(|err| ServiceError::Internal(format!("invalid slot hashes: {}", err)))?;
+    Ok(meta.status(slot, &slot_hashes))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::borrow::Cow;
+
+    #[test]
+    fn test_decode() {
+        let address = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let addresses = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let data = AddressLookupTable {
+            meta: LookupTableMeta::new(authority),
+            addresses: Cow::Borrowed(&addresses),
+        }
+        .serialize_for_tests()
+        .unwrap();
+        let mut account = Account {
+            lamports: 1,
+            data,
+            owner: address_lookup_table::program::id(),
+            executable: false,
+            rent_epoch: 0,
+        };
+
+        let table = decode(&address, &account).unwrap();
+        assert_eq!(table.addresses, addresses);
+        assert!(!table.is_deactivated());
+        assert!(table.check_authority(&authority).is_ok());
+        assert!(matches!(
+            table.check_authority(&Pubkey::new_unique()),
+            Err(ServiceError::InvalidLookupTable { .. })
+        ));
+        assert_eq!(table.account().unwrap().addresses, addresses);
+
+        account.owner = Pubkey::new_unique();
+        assert!(matches!(
+            decode(&address, &account),
+            Err(ServiceError::InvalidLookupTable { .. })
+        ));
+    }
+}
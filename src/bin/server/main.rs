@@ -0,0 +1,1895 @@
+mod auth;
+mod balances;
+mod batch;
+mod cluster;
+mod config;
+mod confirmation;
+mod decoder;
+mod error;
+mod fees;
+mod health;
+mod history;
+mod instruction;
+mod keystore;
+mod landed;
+mod lookup_table;
+mod memo;
+mod metrics;
+mod nonce;
+mod pool;
+mod shutdown;
+mod simulation;
+mod telemetry;
+mod tls;
+mod tracker;
+mod transfer;
+mod versioned;
+
+use auth::{authorize, Authenticator};
+use balances::{balances, MAX_BALANCE_ADDRESSES};
+use batch::{pack, MAX_BATCH_PAYOUTS, MAX_PARALLEL_SUBMISSIONS};
+use borsh::{BorshDeserialize, BorshSerialize};
+use cluster::{cluster_info, Cluster, ClusterRegistry};
+use config::{Config, FeeConfig, DEFAULT_CONFIG_PATH};
+use confirmation::{commitment_config, transaction_result};
+use decoder::DecoderRegistry;
+use error::{parse_keypair, parse_pubkey, parse_signature, parse_transaction, ServiceError};
+use fees::{compute_budget_instructions, message_fee, quote};
+use health::{HealthMonitor, HEALTH_CHECK_INTERVAL};
+use history::{history, DEFAULT_HISTORY_LIMIT, MAX_HISTORY_LIMIT};
+use instruction::ParserRegistry;
+use keystore::{Keystore, WalletInfo};
+use landed::landed;
+use lookup_table::{lookup_table, MAX_EXTEND_ADDRESSES};
+use memo::{memo_instruction, memos};
+use metrics::Metrics;
+use nonce::{nonce_address, nonce_data};
+use pool::RpcClientPool;
+use shutdown::Shutdown;
+use simulation::simulate;
+use tls::server_tls_config;
+use tracker::Tracker;
+use transfer::{Nonce, Transfer};
+use versioned::{sign, v0_message, v0_transaction};
+use solana::solana_service_server::{SolanaService, SolanaServiceServer};
+use solana::{
+    AirdropRequest, AirdropResponse, BalanceRequest, BalanceResponse, CreateWalletRequest,
+    CreateWalletResponse, SendSolRequest, SendSolResponse, GreetRequest, GreetResponse,
+    ListNetworksRequest, ListNetworksResponse, Network, ImportWalletRequest,
+    ImportWalletResponse, ListWalletsRequest, ListWalletsResponse, UnlockWalletRequest,
+    UnlockWalletResponse, LockWalletRequest, LockWalletResponse, DeleteWalletRequest,
+    DeleteWalletResponse, Wallet, TransactionResult, TransactionStatusRequest,
+    TransactionStatusResponse, CreateNonceAccountRequest, CreateNonceAccountResponse,
+    GetNonceAccountRequest, GetNonceAccountResponse, AdvanceNonceAccountRequest,
+    AdvanceNonceAccountResponse, WithdrawNonceAccountRequest, WithdrawNonceAccountResponse,
+    BuildTransferRequest, BuildTransferResponse, SubmitSignedTransactionRequest,
+    SubmitSignedTransactionResponse, SendSolBatchRequest, SendSolBatchResponse, PayoutResult,
+    EstimateFeeRequest, EstimateFeeResponse, estimate_fee_request::Call, CreateLookupTableRequest,
+    CreateLookupTableResponse, ExtendLookupTableRequest, ExtendLookupTableResponse,
+    DeactivateLookupTableRequest, DeactivateLookupTableResponse, CloseLookupTableRequest,
+    CloseLookupTableResponse, GetLookupTableRequest, GetLookupTableResponse, LookupTableState,
+    GetAccountInfoRequest, GetAccountInfoResponse, GetTransactionHistoryRequest,
+    GetTransactionHistoryResponse, GetTransactionRequest, GetTransactionResponse, BalanceChange,
+    GetBalancesRequest, GetBalancesResponse, AddressBalance, GetClusterInfoRequest,
+    GetClusterInfoResponse};
+use futures::stream::{self, StreamExt};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{system_instruction };
+use solana_sdk::{
+    address_lookup_table::{
+        instruction::{close_lookup_table, create_lookup_table, deactivate_lookup_table, extend_lookup_table},
+        state::{LookupTableStatus, LOOKUP_TABLE_MAX_ADDRESSES},
+    },
+    commitment_config::CommitmentConfig,
+    nonce::State as NonceState,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction::transfer,
+    transaction::VersionedTransaction,
+};
+use solana_program::{instruction::Instruction, pubkey};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task;
+use tonic::{transport::Server, Request, Response, Status};
+use tracing::{debug, error, info, warn, Span};
+
+pub mod solana {
+    tonic::include_proto!("solana");
+
+    /// Encoded descriptors of `proto/solana.proto`, served by the reflection service.
+    pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("solana_descriptor");
+}
+
+/// Signatures accepted by one `GetTransactionStatus` call, as for `getSignatureStatuses`.
+const MAX_STATUS_SIGNATURES: usize = 256;
+
+/// Program id of the deployed hello world program from `program/`.
+pub const GREETING_PROGRAM_ID: Pubkey = pubkey!("D36yRZ6n8AwhhStGRJQvjZL78nx5DP2qR3CtqraQuLJF");
+
+/// Lamports deposited into a new greeting account by `Greet`.
+const GREETING_ACCOUNT_LAMPORTS: u64 = 10_000_000;
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Default)]
+pub struct GreetingAccount {
+    pub counter: u32,
+}
+
+pub struct MySolanaService {
+    clusters: Arc<ClusterRegistry>,
+    clients: Arc<RpcClientPool>,
+    keystore: Arc<Keystore>,
+    metrics: Arc<Metrics>,
+    shutdown: Arc<Shutdown>,
+    tracker: Arc<Tracker>,
+    fees: FeeConfig,
+    decoders: Arc<DecoderRegistry>,
+    parsers: Arc<ParserRegistry>,
+    /// Reject calls that did not pass through the auth interceptor.
+    auth_required: bool,
+}
+
+impl MySolanaService {
+    pub fn new(clusters: ClusterRegistry, keystore: Keystore, metrics: Arc<Metrics>) -> Self {
+        let clients = RpcClientPool::new(&clusters, metrics.clone());
+        Self {
+            clusters: Arc::new(clusters),
+            clients: Arc::new(clients),
+            keystore: Arc::new(keystore),
+            tracker: Arc::new(Tracker::new(metrics.clone())),
+            metrics,
+            shutdown: Arc::new(Shutdown::new()),
+            fees: FeeConfig::default(),
+            decoders: Arc::new(DecoderRegistry::new()),
+            parsers: Arc::new(ParserRegistry::new()),
+            auth_required: false,
+        }
+    }
+
+    /// Requires every call to carry a principal attached by `auth::interceptor`.
+    pub fn require_auth(mut self) -> Self {
+        self.auth_required = true;
+        self
+    }
+
+    pub fn with_fees(mut self, fees: FeeConfig) -> Self {
+        self.fees = fees;
+        self
+    }
+
+    /// Runs a keystore operation on the blocking pool, since key derivation is
+    /// deliberately slow and the keystore does file IO.
+    async fn keystore<T, F>(&self, op: F) -> Result<T, ServiceError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Keystore) -> Result<T, ServiceError> + Send + 'static,
+    {
+        let keystore = self.keystore.clone();
+        task::spawn_blocking(move || op(&keystore))
+            .await
+            .map_err(|err| ServiceError::Internal(err.to_string()))?
+    }
+
+    /// Records request metrics for a handler. Unknown networks are reported as
+    /// `unknown` so arbitrary client input cannot create new label values.
+    async fn observe<T, F>(&self, method: &str, network: &str, call: F) -> Result<T, Status>
+    where
+        F: Future<Output = Result<T, Status>>,
+    {
+        let span = Span::current();
+        if !network.is_empty() {
+            span.record("network", network);
+        }
+        let network = match network {
+            "" => "",
+            network => self
+                .clusters
+                .get(network)
+                .map_or("unknown", |cluster| cluster.name.as_str()),
+        };
+
+        let result = self
+            .metrics
+            .observe_request(method, network, async {
+                self.shutdown.check_accepting()?;
+                call.await
+            })
+            .await;
+        match &result {
+            Ok(_) => {
+                span.record("code", "Ok");
+                info!(method, "request completed");
+            }
+            Err(status) => {
+                let code = format!("{:?}", status.code());
+                span.record("code", code.as_str());
+                warn!(method, code, message = status.message(), "request failed");
+            }
+        }
+        result
+    }
+
+    /// Submits a signed transaction and waits until it reaches `commitment`, recording
+    /// how long confirmation took on the cluster. The tracker follows it from there
+    /// until it is finalized.
+    #[tracing::instrument(
+        skip_all,
+        fields(cluster = %cluster.name, signature = %transaction.signatures[0])
+    )]
+    async fn send_and_confirm(
+        &self,
+        cluster: &Cluster,
+        client: &Arc<RpcClient>,
+        transaction: &VersionedTransaction,
+        commitment: CommitmentConfig,
+    ) -> Result<TransactionResult, ServiceError> {
+        let fee = message_fee(client, &transaction.message).await?;
+        // Tracked until the outcome is known, so a shutdown can record it as unconfirmed
+        let pending = self.shutdown.track(
+            &cluster.name,
+            &transaction.signatures[0],
+            transaction.message.recent_blockhash(),
+            nonce_address(transaction).as_ref(),
+        );
+        let start = Instant::now();
+        let signature = confirmation::send(client, transaction, commitment).await?.to_string();
+        self.tracker.submitted(&cluster.name, &signature, memos(&transaction.message));
+
+        let status = match confirmation::confirm(client, transaction, commitment).await {
+            Ok(status) => status,
+            Err(ServiceError::BlockhashExpired) => {
+                self.tracker.expired(&signature);
+                return Err(ServiceError::BlockhashExpired);
+            }
+            Err(err) => {
+                // The transaction may still land, so keep following it in the background
+                self.tracker.spawn(&cluster.name, client.clone(), transaction.clone(), Some(pending));
+                return Err(err);
+            }
+        };
+        drop(pending);
+        self.metrics
+            .observe_confirmation(&cluster.name, start.elapsed());
+        self.tracker.observed(&signature, &status);
+        if !tracker::is_final(tracker::state(&status)) {
+            self.tracker.spawn(&cluster.name, client.clone(), transaction.clone(), None);
+        }
+        if let Some(err) = &status.err {
+            warn!(slot = status.slot, error = %err, "transaction failed on chain");
+        }
+        Ok(transaction_result(client, transaction, &status, fee).await)
+    }
+
+    /// Adds `addresses` to a lookup table, `MAX_EXTEND_ADDRESSES` per transaction, one
+    /// transaction after the other. Stops at the first transaction that failed.
+    async fn extend_table(
+        &self,
+        cluster: &Cluster,
+        client: &Arc<RpcClient>,
+        table: &Pubkey,
+        authority: &Keypair,
+        addresses: &[Pubkey],
+        commitment: CommitmentConfig,
+    ) -> Result<Vec<TransactionResult>, ServiceError> {
+        let mut results = Vec::new();
+        for chunk in addresses.chunks(MAX_EXTEND_ADDRESSES) {
+            let instruction = extend_lookup_table(*table, authority.pubkey(), Some(authority.pubkey()), chunk.to_vec());
+            let blockhash = client.get_latest_blockhash().await?;
+            let transaction = v0_transaction(authority, &[instruction], &[], blockhash)?;
+            let result = self.send_and_confirm(cluster, client, &transaction, commitment).await?;
+            let failed = !result.error.is_empty();
+            results.push(result);
+            if failed {
+                break;
+            }
+        }
+        Ok(results)
+    }
+
+    /// Submits a signed transaction and returns without waiting for it. The tracker
+    /// re-broadcasts it until it lands or its blockhash expires.
+    #[tracing::instrument(
+        skip_all,
+        fields(cluster = %cluster.name, signature = %transaction.signatures[0])
+    )]
+    async fn submit(
+        &self,
+        cluster: &Cluster,
+        client: &Arc<RpcClient>,
+        transaction: VersionedTransaction,
+        commitment: CommitmentConfig,
+    ) -> Result<TransactionResult, ServiceError> {
+        let fee = message_fee(client, &transaction.message).await?;
+        let pending = self.shutdown.track(
+            &cluster.name,
+            &transaction.signatures[0],
+            transaction.message.recent_blockhash(),
+            nonce_address(&transaction).as_ref(),
+        );
+        let signature = confirmation::send(client, &transaction, commitment).await?.to_string();
+        self.tracker.submitted(&cluster.name, &signature, memos(&transaction.message));
+        self.tracker.spawn(&cluster.name, client.clone(), transaction, Some(pending));
+
+        Ok(TransactionResult {
+            signature,
+            fee,
+            ..TransactionResult::default()
+        })
+    }
+
+    /// Resolves the `network` of a request to its cluster and the shared client for it.
+    fn client(&self, network: &str) -> Result<(&Cluster, Arc<RpcClient>), ServiceError> {
+        let cluster = self.clusters.resolve(network)?;
+        let client = self
+            .clients
+            .get(&cluster.name)
+            .ok_or_else(|| ServiceError::UnknownNetwork {
+                network: network.to_string(),
+                configured: self.clusters.names().join(", "),
+            })?;
+        Ok((cluster, client))
+    }
+}
+
+#[tonic::async_trait]
+impl SolanaService for MySolanaService {
+    async fn get_balance(
+        &self,
+        request: Request<BalanceRequest>,
+    ) -> Result<Response<BalanceResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("GetBalance", &network, async {
+            authorize(&request, self.auth_required, "GetBalance", Some(&network))?;
+            let BalanceRequest {
+                network,
+                wallet_address,
+            } = request.into_inner();
+            let (_, client) = self.client(&network)?;
+            let pubkey = parse_pubkey("wallet_address", &wallet_address)?;
+
+            let balance = client.get_balance(&pubkey).await.map_err(ServiceError::from)?;
+            let response = BalanceResponse { balance };
+
+            Ok(Response::new(response))
+        })
+        .await
+    }
+
+    async fn get_balances(
+        &self,
+        request: Request<GetBalancesRequest>,
+    ) -> Result<Response<GetBalancesResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("GetBalances", &network, async {
+            authorize(&request, self.auth_required, "GetBalances", Some(&network))?;
+            let GetBalancesRequest {
+                network,
+                wallet_addresses,
+                commitment,
+            } = request.into_inner();
+            let (_, client) = self.client(&network)?;
+            let commitment = commitment_config(
+                solana::Commitment::try_from(commitment).unwrap_or_default(),
+                &client,
+            );
+            if wallet_addresses.len() > MAX_BALANCE_ADDRESSES {
+                return Err(ServiceError::InvalidArgument {
+                    field: "wallet_addresses",
+                    reason: format!("at most {} addresses per call", MAX_BALANCE_ADDRESSES),
+                }
+                .into());
+            }
+
+            // Invalid addresses are reported in place instead of failing the whole call
+            let pubkeys: Vec<_> = wallet_addresses
+                .iter()
+                .map(|address| parse_pubkey("wallet_addresses", address))
+                .collect();
+            let valid: Vec<_> = pubkeys.iter().filter_map(|pubkey| pubkey.as_ref().ok().copied()).collect();
+            let (lamports, slot) = balances(&client, &valid, commitment).await?;
+
+            let mut lamports = lamports.into_iter();
+            let balances = wallet_addresses
+                .into_iter()
+                .zip(pubkeys)
+                .map(|(address, pubkey)| match pubkey {
+                    Ok(_) => AddressBalance {
+                        address,
+                        lamports: lamports.next().unwrap_or_default(),
+                        error: String::new(),
+                    },
+                    Err(err) => AddressBalance {
+                        address,
+                        lamports: 0,
+                        error: err.to_string(),
+                    },
+                })
+                .collect();
+
+            Ok(Response::new(GetBalancesResponse { balances, slot }))
+        })
+        .await
+    }
+
+    async fn create_wallet(
+        &self,
+        request: Request<CreateWalletRequest>,
+    ) -> Result<Response<CreateWalletResponse>, Status> {
+        self.observe("CreateWallet", "", async {
+            authorize(&request, self.auth_required, "CreateWallet", None)?;
+            let CreateWalletRequest { passphrase } = request.into_inner();
+
+            // The keypair never leaves the server, an empty passphrase is rejected
+            let wallet = self.keystore(move |keystore| keystore.create(&passphrase)).await?;
+            let response = CreateWalletResponse {
+                public_key: wallet.public_key,
+                wallet_id: wallet.id,
+            };
+            Ok(Response::new(response))
+        })
+        .await
+    }
+
+    async fn request_airdrop(
+        &self,
+        request: Request<AirdropRequest>,
+    ) -> Result<Response<AirdropResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("RequestAirdrop", &network, async {
+            authorize(&request, self.auth_required, "RequestAirdrop", Some(&network))?;
+            let AirdropRequest {
+                network,
+                wallet_address,
+                amount,
+            } = request.into_inner();
+
+            // Only clusters configured with a faucet accept airdrop requests
+            let (cluster, client) = self.client(&network)?;
+            if !cluster.faucet {
+                return Err(ServiceError::FaucetUnavailable(cluster.name.clone()).into());
+            }
+            let pubkey = parse_pubkey("wallet_address", &wallet_address)?;
+
+            let signature = client
+                .request_airdrop(&pubkey, amount)
+                .await
+                .map_err(|err| {
+                    self.metrics.airdrop_failed(&cluster.name);
+                    ServiceError::from(err)
+                })?;
+            let response = AirdropResponse {
+                signature: signature.to_string(),
+            };
+
+            Ok(Response::new(response))
+        })
+        .await
+    }
+
+    async fn send_sol(
+        &self,
+        request: Request<SendSolRequest>,
+    ) -> Result<Response<SendSolResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("SendSol", &network, async {
+            authorize(&request, self.auth_required, "SendSol", Some(&network))?;
+            let SendSolRequest {
+                to_address,
+                amount,
+                network,
+                wallet_id,
+                dry_run,
+                compute_budget,
+                commitment,
+                no_wait,
+                nonce_address,
+                nonce_authority_wallet_id,
+                memo,
+            } = request.into_inner();
+            let (cluster, client) = self.client(&network)?;
+            let commitment = commitment_config(
+                solana::Commitment::try_from(commitment).unwrap_or_default(),
+                &client,
+            );
+            let to_pubkey = parse_pubkey("to_address", &to_address)?;
+            let from_keypair = self.keystore.signer(&wallet_id)?;
+            let from_pubkey = from_keypair.pubkey();
+
+            let mut signers = vec![from_keypair.clone()];
+            let nonce = if nonce_address.is_empty() {
+                None
+            } else {
+                let authority = match nonce_authority_wallet_id.as_str() {
+                    "" => from_keypair.clone(),
+                    wallet_id => self.keystore.signer(wallet_id)?,
+                };
+                let nonce = Nonce {
+                    address: parse_pubkey("nonce_address", &nonce_address)?,
+                    authority: authority.pubkey(),
+                };
+                if authority.pubkey() != from_pubkey {
+                    signers.push(authority);
+                }
+                Some(nonce)
+            };
+            let transfer = Transfer {
+                from: from_pubkey,
+                to: to_pubkey,
+                amount,
+                compute_budget: compute_budget.as_ref(),
+                nonce,
+                memo: &memo,
+            };
+            let message = transfer.build(&client, &self.fees, commitment).await?.message;
+            let signers: Vec<&Keypair> = signers.iter().map(|signer| signer.as_ref()).collect();
+            let tx = sign(message, &signers)?;
+
+            if dry_run {
+                let fee = message_fee(&client, &tx.message).await?;
+                let simulation = simulate(&client, &tx).await?;
+                let response = SendSolResponse {
+                    simulation: Some(simulation),
+                    fee,
+                    result: None,
+                };
+                return Ok(Response::new(response));
+            }
+
+            if no_wait {
+                let result = self.submit(cluster, &client, tx, commitment).await?;
+                info!(from = %from_pubkey, to = %to_pubkey, amount, signature = %result.signature, "transfer submitted");
+                let response = SendSolResponse {
+                    simulation: None,
+                    fee: result.fee,
+                    result: Some(result),
+                };
+                return Ok(Response::new(response));
+            }
+
+            let result = self.send_and_confirm(cluster, &client, &tx, commitment).await?;
+            info!(
+                from = %from_pubkey,
+                to = %to_pubkey,
+                amount,
+                fee = result.fee,
+                signature = %result.signature,
+                slot = result.slot,
+                "transfer confirmed"
+            );
+            let response = SendSolResponse {
+                simulation: None,
+                fee: result.fee,
+                result: Some(result),
+            };
+
+            Ok(Response::new(response))
+        })
+        .await
+    }
+
+    async fn send_sol_batch(
+        &self,
+        request: Request<SendSolBatchRequest>,
+    ) -> Result<Response<SendSolBatchResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("SendSolBatch", &network, async {
+            authorize(&request, self.auth_required, "SendSolBatch", Some(&network))?;
+            let SendSolBatchRequest {
+                network,
+                wallet_id,
+                payouts,
+                compute_budget,
+                commitment,
+                lookup_table_address,
+            } = request.into_inner();
+            let (cluster, client) = self.client(&network)?;
+            let commitment = commitment_config(
+                solana::Commitment::try_from(commitment).unwrap_or_default(),
+                &client,
+            );
+            if payouts.is_empty() || payouts.len() > MAX_BATCH_PAYOUTS {
+                return Err(ServiceError::InvalidArgument {
+                    field: "payouts",
+                    reason: format!("expected 1 to {} payouts, got {}", MAX_BATCH_PAYOUTS, payouts.len()),
+                }
+                .into());
+            }
+            let payer = self.keystore.signer(&wallet_id)?;
+            let instructions = payouts
+                .iter()
+                .map(|payout| {
+                    let to = parse_pubkey("payouts", &payout.to_address)?;
+                    let mut instructions: Vec<_> =
+                        memo_instruction("payouts", &payout.memo, &payer.pubkey())?.into_iter().collect();
+                    instructions.push(transfer(&payer.pubkey(), &to, payout.amount));
+                    Ok(instructions)
+                })
+                .collect::<Result<Vec<_>, ServiceError>>()?;
+
+            // Auto pricing looks at the payer only: a fee lookup takes at most 128 accounts
+            let budget =
+                compute_budget_instructions(&client, &self.fees, compute_budget.as_ref(), &[payer.pubkey()]).await?;
+            let lookup_tables = match lookup_table_address.as_str() {
+                "" => Vec::new(),
+                address => {
+                    let address = parse_pubkey("lookup_table_address", address)?;
+                    vec![lookup_table(&client, &address, commitment).await?.account()?]
+                }
+            };
+            let chunks = pack(&payer.pubkey(), &budget, &instructions, &lookup_tables);
+            info!(payouts = payouts.len(), transactions = chunks.len(), "submitting batch");
+
+            // Futures are lazy, so at most MAX_PARALLEL_SUBMISSIONS of these run at once.
+            // Each transaction is signed when its turn comes: a blockhash fetched up front
+            // could expire while earlier rounds wait for confirmation.
+            let (client, payer, budget, instructions, lookup_tables) =
+                (&client, &payer, &budget, &instructions, &lookup_tables);
+            let submissions: Vec<_> = chunks
+                .iter()
+                .map(|chunk| async move {
+                    let instructions: Vec<_> = budget
+                        .iter()
+                        .cloned()
+                        .chain(instructions[chunk.clone()].concat())
+                        .collect();
+                    let transaction = match client.get_latest_blockhash().await {
+                        Ok(blockhash) => v0_transaction(payer, &instructions, lookup_tables, blockhash),
+                        Err(err) => Err(err.into()),
+                    };
+                    match transaction {
+                        Ok(transaction) => {
+                            let outcome = self.send_and_confirm(cluster, client, &transaction, commitment).await;
+                            (transaction.signatures[0].to_string(), outcome)
+                        }
+                        Err(err) => (String::new(), Err(err)),
+                    }
+                })
+                .collect();
+            let outcomes: Vec<_> = stream::iter(submissions)
+                .buffered(MAX_PARALLEL_SUBMISSIONS)
+                .collect()
+                .await;
+
+            let mut response = SendSolBatchResponse::default();
+            for (chunk, (signature, outcome)) in chunks.into_iter().zip(outcomes) {
+                let error = match outcome {
+                    Ok(result) => {
+                        let error = result.error.clone();
+                        response.fee += result.fee;
+                        response.transactions.push(result);
+                        error
+                    }
+                    Err(err) => {
+                        warn!(%signature, error = %err, "batch transaction failed");
+                        err.to_string()
+                    }
+                };
+                response.results.extend(payouts[chunk].iter().map(|payout| PayoutResult {
+                    to_address: payout.to_address.clone(),
+                    amount: payout.amount,
+                    signature: signature.clone(),
+                    error: error.clone(),
+                }));
+            }
+
+            Ok(Response::new(response))
+        })
+        .await
+    }
+
+    async fn greet(&self, request: Request<GreetRequest>) -> Result<Response<GreetResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("Greet", &network, async {
+            authorize(&request, self.auth_required, "Greet", Some(&network))?;
+            let GreetRequest {
+                network,
+                seed,
+                wallet_id,
+                dry_run,
+                compute_budget,
+                commitment,
+            } = request.into_inner();
+
+            //establish connection to the network
+            let (cluster, client) = self.client(&network)?;
+            let commitment = commitment_config(
+                solana::Commitment::try_from(commitment).unwrap_or_default(),
+                &client,
+            );
+            //converting the secret key to a keypair.pubkey and searching for the balance
+            let payer = self.keystore.signer(&wallet_id)?;
+            let lamports = client.get_balance(&payer.pubkey()).await.map_err(ServiceError::from)?;
+            debug!(payer = %payer.pubkey(), lamports, "payer balance");
+
+            let Greeting {
+                greeted_pubkey,
+                create_account,
+                instruction,
+            } = greeting(&client, &payer.pubkey(), &seed).await?;
+
+            // Both transactions write to the payer and the greeted account, so they share one budget
+            let budget = compute_budget_instructions(
+                &client,
+                &self.fees,
+                compute_budget.as_ref(),
+                &[payer.pubkey(), greeted_pubkey],
+            )
+            .await?;
+
+            // A dry run simulates both steps in a single transaction, since the greeting
+            // depends on the account created by the first one
+            if dry_run {
+                let instructions: Vec<_> = budget.into_iter().chain(create_account).chain([instruction]).collect();
+                let transaction = v0_transaction(
+                    &payer,
+                    &instructions,
+                    &[],
+                    client.get_latest_blockhash().await.map_err(ServiceError::from)?,
+                )?;
+                let fee = message_fee(&client, &transaction.message).await?;
+                let simulation = simulate(&client, &transaction).await?;
+                let response = GreetResponse {
+                    simulation: Some(simulation),
+                    fee,
+                    greeted_address: greeted_pubkey.to_string(),
+                    ..GreetResponse::default()
+                };
+                return Ok(Response::new(response));
+            }
+
+            let mut response = GreetResponse {
+                greeted_address: greeted_pubkey.to_string(),
+                ..GreetResponse::default()
+            };
+            if let Some(create_account) = create_account {
+                let instructions: Vec<_> = budget.iter().cloned().chain([create_account]).collect();
+                let transaction = v0_transaction(
+                    &payer,
+                    &instructions,
+                    &[],
+                    client.get_latest_blockhash().await.map_err(ServiceError::from)?,
+                )?;
+
+                let result = self.send_and_confirm(cluster, &client, &transaction, commitment).await?;
+                info!(greeted = %greeted_pubkey, signature = %result.signature, "created greeting account");
+                response.fee += result.fee;
+                // Greeting an account that does not exist would only fail again
+                let failed = !result.error.is_empty();
+                response.create_account = Some(result);
+                if failed {
+                    return Ok(Response::new(response));
+                }
+            } else {
+                debug!(greeted = %greeted_pubkey, "greeting account already exists");
+            }
+
+            let recent_blockhash = client.get_latest_blockhash().await.map_err(ServiceError::from)?;
+            let instructions: Vec<_> = budget.into_iter().chain([instruction]).collect();
+            let transaction = v0_transaction(&payer, &instructions, &[], recent_blockhash)?;
+
+            let result = self.send_and_confirm(cluster, &client, &transaction, commitment).await?;
+            info!(greeted = %greeted_pubkey, signature = %result.signature, fee = result.fee, "greeted");
+            response.fee += result.fee;
+            response.result = Some(result);
+
+            match read_greetings(&client, &greeted_pubkey, commitment).await {
+                Ok(counter) => response.counter = counter,
+                Err(err) => warn!(greeted = %greeted_pubkey, error = %err, "failed to read greeting counter"),
+            }
+
+            Ok(Response::new(response))
+        })
+        .await
+    }
+
+    async fn estimate_fee(
+        &self,
+        request: Request<EstimateFeeRequest>,
+    ) -> Result<Response<EstimateFeeResponse>, Status> {
+        let network = match &request.get_ref().call {
+            Some(Call::SendSol(call)) => call.network.clone(),
+            Some(Call::Greet(call)) => call.network.clone(),
+            None => String::new(),
+        };
+        self.observe("EstimateFee", &network, async {
+            authorize(&request, self.auth_required, "EstimateFee", Some(&network))?;
+            let (_, client) = self.client(&network)?;
+
+            // Messages the call would submit, built exactly as the call builds them but
+            // for public keys only, so wallets do not need to be unlocked
+            let mut messages = Vec::new();
+            let mut rent = 0;
+            match request.into_inner().call {
+                Some(Call::SendSol(call)) => {
+                    let commitment = commitment_config(
+                        solana::Commitment::try_from(call.commitment).unwrap_or_default(),
+                        &client,
+                    );
+                    let from_pubkey = self.keystore.public_key(&call.wallet_id)?;
+                    let nonce = if call.nonce_address.is_empty() {
+                        None
+                    } else {
+                        Some(Nonce {
+                            address: parse_pubkey("nonce_address", &call.nonce_address)?,
+                            authority: match call.nonce_authority_wallet_id.as_str() {
+                                "" => from_pubkey,
+                                wallet_id => self.keystore.public_key(wallet_id)?,
+                            },
+                        })
+                    };
+                    let transfer = Transfer {
+                        from: from_pubkey,
+                        to: parse_pubkey("to_address", &call.to_address)?,
+                        amount: call.amount,
+                        compute_budget: call.compute_budget.as_ref(),
+                        nonce,
+                        memo: &call.memo,
+                    };
+                    messages.push(transfer.build(&client, &self.fees, commitment).await?.message);
+                }
+                Some(Call::Greet(call)) => {
+                    let payer = self.keystore.public_key(&call.wallet_id)?;
+                    let greeting = greeting(&client, &payer, &call.seed).await?;
+                    let budget = compute_budget_instructions(
+                        &client,
+                        &self.fees,
+                        call.compute_budget.as_ref(),
+                        &[payer, greeting.greeted_pubkey],
+                    )
+                    .await?;
+                    let blockhash = client.get_latest_blockhash().await.map_err(ServiceError::from)?;
+                    if let Some(create_account) = greeting.create_account {
+                        rent += GREETING_ACCOUNT_LAMPORTS;
+                        let instructions: Vec<_> = budget.iter().cloned().chain([create_account]).collect();
+                        messages.push(v0_message(&payer, &instructions, &[], blockhash)?);
+                    }
+                    let instructions: Vec<_> = budget.into_iter().chain([greeting.instruction]).collect();
+                    messages.push(v0_message(&payer, &instructions, &[], blockhash)?);
+                }
+                None => {
+                    return Err(ServiceError::InvalidArgument {
+                        field: "call",
+                        reason: "set send_sol or greet".to_string(),
+                    }
+                    .into());
+                }
+            }
+
+            let mut response = EstimateFeeResponse {
+                rent,
+                transactions: messages.len() as u32,
+                ..EstimateFeeResponse::default()
+            };
+            for message in &messages {
+                let (base_fee, priority_fee) = quote(&client, message).await?;
+                response.base_fee += base_fee;
+                response.priority_fee += priority_fee;
+            }
+            response.total = response.base_fee + response.priority_fee + response.rent;
+
+            Ok(Response::new(response))
+        })
+        .await
+    }
+
+    async fn get_cluster_info(
+        &self,
+        request: Request<GetClusterInfoRequest>,
+    ) -> Result<Response<GetClusterInfoResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("GetClusterInfo", &network, async {
+            authorize(&request, self.auth_required, "GetClusterInfo", Some(&network))?;
+            let GetClusterInfoRequest {
+                network,
+                commitment,
+            } = request.into_inner();
+            let (cluster, client) = self.client(&network)?;
+            let commitment = commitment_config(
+                solana::Commitment::try_from(commitment).unwrap_or_default(),
+                &client,
+            );
+
+            let response = cluster_info(cluster, &client, commitment).await?;
+
+            Ok(Response::new(response))
+        })
+        .await
+    }
+
+    async fn list_networks(
+        &self,
+        request: Request<ListNetworksRequest>,
+    ) -> Result<Response<ListNetworksResponse>, Status> {
+        self.observe("ListNetworks", "", async {
+            authorize(&request, self.auth_required, "ListNetworks", None)?;
+            let networks = self
+                .clusters
+                .iter()
+                .map(|cluster| Network {
+                    name: cluster.name.clone(),
+                    rpc_url: cluster.rpc_url.clone(),
+                    ws_url: cluster.ws_url.clone(),
+                    faucet: cluster.faucet,
+                })
+                .collect();
+
+            Ok(Response::new(ListNetworksResponse { networks }))
+        })
+        .await
+    }
+
+    async fn import_wallet(
+        &self,
+        request: Request<ImportWalletRequest>,
+    ) -> Result<Response<ImportWalletResponse>, Status> {
+        self.observe("ImportWallet", "", async {
+            authorize(&request, self.auth_required, "ImportWallet", None)?;
+            let ImportWalletRequest {
+                secret_key,
+                passphrase,
+            } = request.into_inner();
+            let keypair = parse_keypair("secret_key", &secret_key)?;
+
+            let wallet = self
+                .keystore(move |keystore| keystore.store(keypair, &passphrase))
+                .await?;
+            let response = ImportWalletResponse {
+                wallet_id: wallet.id,
+                public_key: wallet.public_key,
+            };
+
+            Ok(Response::new(response))
+        })
+        .await
+    }
+
+    async fn list_wallets(
+        &self,
+        request: Request<ListWalletsRequest>,
+    ) -> Result<Response<ListWalletsResponse>, Status> {
+        self.observe("ListWallets", "", async {
+            authorize(&request, self.auth_required, "ListWallets", None)?;
+            let wallets = self.keystore(|keystore| keystore.list()).await?;
+            let wallets = wallets.into_iter().map(wallet_message).collect();
+
+            Ok(Response::new(ListWalletsResponse { wallets }))
+        })
+        .await
+    }
+
+    async fn unlock_wallet(
+        &self,
+        request: Request<UnlockWalletRequest>,
+    ) -> Result<Response<UnlockWalletResponse>, Status> {
+        self.observe("UnlockWallet", "", async {
+            authorize(&request, self.auth_required, "UnlockWallet", None)?;
+            let UnlockWalletRequest {
+                wallet_id,
+                passphrase,
+            } = request.into_inner();
+
+            let wallet = self
+                .keystore(move |keystore| keystore.unlock(&wallet_id, &passphrase))
+                .await?;
+            let response = UnlockWalletResponse {
+                wallet: Some(wallet_message(wallet)),
+            };
+
+            Ok(Response::new(response))
+        })
+        .await
+    }
+
+    async fn lock_wallet(
+        &self,
+        request: Request<LockWalletRequest>,
+    ) -> Result<Response<LockWalletResponse>, Status> {
+        self.observe("LockWallet", "", async {
+            authorize(&request, self.auth_required, "LockWallet", None)?;
+            let LockWalletRequest { wallet_id } = request.into_inner();
+            self.keystore(move |keystore| keystore.lock(&wallet_id)).await?;
+
+            Ok(Response::new(LockWalletResponse {}))
+        })
+        .await
+    }
+
+    async fn delete_wallet(
+        &self,
+        request: Request<DeleteWalletRequest>,
+    ) -> Result<Response<DeleteWalletResponse>, Status> {
+        self.observe("DeleteWallet", "", async {
+            authorize(&request, self.auth_required, "DeleteWallet", None)?;
+            let DeleteWalletRequest {
+                wallet_id,
+                passphrase,
+            } = request.into_inner();
+            self.keystore(move |keystore| keystore.delete(&wallet_id, &passphrase))
+                .await?;
+
+            Ok(Response::new(DeleteWalletResponse {}))
+        })
+        .await
+    }
+
+    async fn create_nonce_account(
+        &self,
+        request: Request<CreateNonceAccountRequest>,
+    ) -> Result<Response<CreateNonceAccountResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("CreateNonceAccount", &network, async {
+            authorize(&request, self.auth_required, "CreateNonceAccount", Some(&network))?;
+            let CreateNonceAccountRequest {
+                network,
+                wallet_id,
+                authority,
+                lamports,
+                commitment,
+            } = request.into_inner();
+            let (cluster, client) = self.client(&network)?;
+            let commitment = commitment_config(
+                solana::Commitment::try_from(commitment).unwrap_or_default(),
+                &client,
+            );
+            let payer = self.keystore.signer(&wallet_id)?;
+            let authority = match authority.as_str() {
+                "" => payer.pubkey(),
+                authority => parse_pubkey("authority", authority)?,
+            };
+            let lamports = match lamports {
+                0 => client
+                    .get_minimum_balance_for_rent_exemption(NonceState::size())
+                    .await
+                    .map_err(ServiceError::from)?,
+                lamports => lamports,
+            };
+
+            // Nonce accounts are authorized by `authority`, so their own key is not kept
+            let nonce_keypair = Keypair::new();
+            let message = v0_message(
+                &payer.pubkey(),
+                &system_instruction::create_nonce_account(
+                    &payer.pubkey(),
+                    &nonce_keypair.pubkey(),
+                    &authority,
+                    lamports,
+                ),
+                &[],
+                client.get_latest_blockhash().await.map_err(ServiceError::from)?,
+            )?;
+            let transaction = sign(message, &[payer.as_ref(), &nonce_keypair])?;
+            let result = self.send_and_confirm(cluster, &client, &transaction, commitment).await?;
+            info!(nonce = %nonce_keypair.pubkey(), %authority, lamports, signature = %result.signature, "created nonce account");
+            let response = CreateNonceAccountResponse {
+                nonce_address: nonce_keypair.pubkey().to_string(),
+                result: Some(result),
+            };
+
+            Ok(Response::new(response))
+        })
+        .await
+    }
+
+    async fn get_nonce_account(
+        &self,
+        request: Request<GetNonceAccountRequest>,
+    ) -> Result<Response<GetNonceAccountResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("GetNonceAccount", &network, async {
+            authorize(&request, self.auth_required, "GetNonceAccount", Some(&network))?;
+            let GetNonceAccountRequest {
+                network,
+                nonce_address,
+            } = request.into_inner();
+            let (_, client) = self.client(&network)?;
+            let nonce_pubkey = parse_pubkey("nonce_address", &nonce_address)?;
+
+            let nonce = nonce_data(&client, &nonce_pubkey, client.commitment()).await?;
+            let lamports = client.get_balance(&nonce_pubkey).await.map_err(ServiceError::from)?;
+            let response = GetNonceAccountResponse {
+                authority: nonce.authority.to_string(),
+                nonce: nonce.blockhash().to_string(),
+                lamports_per_signature: nonce.get_lamports_per_signature(),
+                lamports,
+            };
+
+            Ok(Response::new(response))
+        })
+        .await
+    }
+
+    async fn advance_nonce_account(
+        &self,
+        request: Request<AdvanceNonceAccountRequest>,
+    ) -> Result<Response<AdvanceNonceAccountResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("AdvanceNonceAccount", &network, async {
+            authorize(&request, self.auth_required, "AdvanceNonceAccount", Some(&network))?;
+            let AdvanceNonceAccountRequest {
+                network,
+                nonce_address,
+                wallet_id,
+                commitment,
+            } = request.into_inner();
+            let (cluster, client) = self.client(&network)?;
+            let commitment = commitment_config(
+                solana::Commitment::try_from(commitment).unwrap_or_default(),
+                &client,
+            );
+            let nonce_pubkey = parse_pubkey("nonce_address", &nonce_address)?;
+            let authority = self.keystore.signer(&wallet_id)?;
+
+            let transaction = v0_transaction(
+                &authority,
+                &[system_instruction::advance_nonce_account(&nonce_pubkey, &authority.pubkey())],
+                &[],
+                client.get_latest_blockhash().await.map_err(ServiceError::from)?,
+            )?;
+            let result = self.send_and_confirm(cluster, &client, &transaction, commitment).await?;
+            let nonce = nonce_data(&client, &nonce_pubkey, commitment).await?;
+            info!(nonce = %nonce_pubkey, signature = %result.signature, "advanced nonce");
+            let response = AdvanceNonceAccountResponse {
+                nonce: nonce.blockhash().to_string(),
+                result: Some(result),
+            };
+
+            Ok(Response::new(response))
+        })
+        .await
+    }
+
+    async fn withdraw_nonce_account(
+        &self,
+        request: Request<WithdrawNonceAccountRequest>,
+    ) -> Result<Response<WithdrawNonceAccountResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("WithdrawNonceAccount", &network, async {
+            authorize(&request, self.auth_required, "WithdrawNonceAccount", Some(&network))?;
+            let WithdrawNonceAccountRequest {
+                network,
+                nonce_address,
+                wallet_id,
+                to_address,
+                amount,
+                commitment,
+                memo,
+            } = request.into_inner();
+            let (cluster, client) = self.client(&network)?;
+            let commitment = commitment_config(
+                solana::Commitment::try_from(commitment).unwrap_or_default(),
+                &client,
+            );
+            let nonce_pubkey = parse_pubkey("nonce_address", &nonce_address)?;
+            let to_pubkey = parse_pubkey("to_address", &to_address)?;
+            let authority = self.keystore.signer(&wallet_id)?;
+
+            let mut instructions: Vec<_> = memo_instruction("memo", &memo, &authority.pubkey())?.into_iter().collect();
+            instructions.push(system_instruction::withdraw_nonce_account(
+                &nonce_pubkey,
+                &authority.pubkey(),
+                &to_pubkey,
+                amount,
+            ));
+            let transaction = v0_transaction(
+                &authority,
+                &instructions,
+                &[],
+                client.get_latest_blockhash().await.map_err(ServiceError::from)?,
+            )?;
+            let result = self.send_and_confirm(cluster, &client, &transaction, commitment).await?;
+            info!(nonce = %nonce_pubkey, to = %to_pubkey, amount, signature = %result.signature, "withdrew from nonce account");
+
+            Ok(Response::new(WithdrawNonceAccountResponse { result: Some(result) }))
+        })
+        .await
+    }
+
+    async fn create_lookup_table(
+        &self,
+        request: Request<CreateLookupTableRequest>,
+    ) -> Result<Response<CreateLookupTableResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("CreateLookupTable", &network, async {
+            authorize(&request, self.auth_required, "CreateLookupTable", Some(&network))?;
+            let CreateLookupTableRequest {
+                network,
+                wallet_id,
+                addresses,
+                commitment,
+            } = request.into_inner();
+            let (cluster, client) = self.client(&network)?;
+            let commitment = commitment_config(
+                solana::Commitment::try_from(commitment).unwrap_or_default(),
+                &client,
+            );
+            let addresses = parse_table_addresses(&addresses, &[])?;
+            let authority = self.keystore.signer(&wallet_id)?;
+
+            // The address derives from a recent slot, which must be known to every fork
+            let recent_slot = client
+                .get_slot_with_commitment(CommitmentConfig::finalized())
+                .await
+                .map_err(ServiceError::from)?;
+            let (create, table) = create_lookup_table(authority.pubkey(), authority.pubkey(), recent_slot);
+            let (first, rest) = addresses.split_at(addresses.len().min(MAX_EXTEND_ADDRESSES));
+            let mut instructions = vec![create];
+            if !first.is_empty() {
+                instructions.push(extend_lookup_table(table, authority.pubkey(), Some(authority.pubkey()), first.to_vec()));
+            }
+            let transaction = v0_transaction(
+                &authority,
+                &instructions,
+                &[],
+                client.get_latest_blockhash().await.map_err(ServiceError::from)?,
+            )?;
+            let result = self.send_and_confirm(cluster, &client, &transaction, commitment).await?;
+            info!(%table, addresses = first.len(), signature = %result.signature, "created lookup table");
+            let mut results = vec![result];
+            if results[0].error.is_empty() {
+                results.extend(self.extend_table(cluster, &client, &table, &authority, rest, commitment).await?);
+            }
+
+            Ok(Response::new(CreateLookupTableResponse {
+                lookup_table_address: table.to_string(),
+                results,
+            }))
+        })
+        .await
+    }
+
+    async fn extend_lookup_table(
+        &self,
+        request: Request<ExtendLookupTableRequest>,
+    ) -> Result<Response<ExtendLookupTableResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("ExtendLookupTable", &network, async {
+            authorize(&request, self.auth_required, "ExtendLookupTable", Some(&network))?;
+            let ExtendLookupTableRequest {
+                network,
+                lookup_table_address,
+                wallet_id,
+                addresses,
+                commitment,
+            } = request.into_inner();
+            let (cluster, client) = self.client(&network)?;
+            let commitment = commitment_config(
+                solana::Commitment::try_from(commitment).unwrap_or_default(),
+                &client,
+            );
+            let table_pubkey = parse_pubkey("lookup_table_address", &lookup_table_address)?;
+            let authority = self.keystore.signer(&wallet_id)?;
+            let table = lookup_table(&client, &table_pubkey, commitment).await?;
+            table.check_authority(&authority.pubkey())?;
+            if table.is_deactivated() {
+                return Err(table.invalid("table is deactivated".to_string()).into());
+            }
+            let addresses = parse_table_addresses(&addresses, &table.addresses)?;
+
+            let results = self
+                .extend_table(cluster, &client, &table_pubkey, &authority, &addresses, commitment)
+                .await?;
+            info!(table = %table_pubkey, addresses = addresses.len(), transactions = results.len(), "extended lookup table");
+
+            Ok(Response::new(ExtendLookupTableResponse { results }))
+        })
+        .await
+    }
+
+    async fn deactivate_lookup_table(
+        &self,
+        request: Request<DeactivateLookupTableRequest>,
+    ) -> Result<Response<DeactivateLookupTableResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("DeactivateLookupTable", &network, async {
+            authorize(&request, self.auth_required, "DeactivateLookupTable", Some(&network))?;
+            let DeactivateLookupTableRequest {
+                network,
+                lookup_table_address,
+                wallet_id,
+                commitment,
+            } = request.into_inner();
+            let (cluster, client) = self.client(&network)?;
+            let commitment = commitment_config(
+                solana::Commitment::try_from(commitment).unwrap_or_default(),
+                &client,
+            );
+            let table_pubkey = parse_pubkey("lookup_table_address", &lookup_table_address)?;
+            let authority = self.keystore.signer(&wallet_id)?;
+            let table = lookup_table(&client, &table_pubkey, commitment).await?;
+            table.check_authority(&authority.pubkey())?;
+            if table.is_deactivated() {
+                return Err(table.invalid("table is already deactivated".to_string()).into());
+            }
+
+            let transaction = v0_transaction(
+                &authority,
+                &[deactivate_lookup_table(table_pubkey, authority.pubkey())],
+                &[],
+                client.get_latest_blockhash().await.map_err(ServiceError::from)?,
+            )?;
+            let result = self.send_and_confirm(cluster, &client, &transaction, commitment).await?;
+            info!(table = %table_pubkey, signature = %result.signature, "deactivated lookup table");
+
+            Ok(Response::new(DeactivateLookupTableResponse { result: Some(result) }))
+        })
+        .await
+    }
+
+    async fn close_lookup_table(
+        &self,
+        request: Request<CloseLookupTableRequest>,
+    ) -> Result<Response<CloseLookupTableResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("CloseLookupTable", &network, async {
+            authorize(&request, self.auth_required, "CloseLookupTable", Some(&network))?;
+            let CloseLookupTableRequest {
+                network,
+                lookup_table_address,
+                wallet_id,
+                recipient_address,
+                commitment,
+            } = request.into_inner();
+            let (cluster, client) = self.client(&network)?;
+            let commitment = commitment_config(
+                solana::Commitment::try_from(commitment).unwrap_or_default(),
+                &client,
+            );
+            let table_pubkey = parse_pubkey("lookup_table_address", &lookup_table_address)?;
+            let authority = self.keystore.signer(&wallet_id)?;
+            let recipient = match recipient_address.as_str() {
+                "" => authority.pubkey(),
+                address => parse_pubkey("recipient_address", address)?,
+            };
+            let table = lookup_table(&client, &table_pubkey, commitment).await?;
+            table.check_authority(&authority.pubkey())?;
+            match lookup_table::status(&client, &table.meta).await? {
+                LookupTableStatus::Activated => {
+                    return Err(table.invalid("table must be deactivated first".to_string()).into());
+                }
+                LookupTableStatus::Deactivating { remaining_blocks } => {
+                    return Err(table
+                        .invalid(format!("table is cooling down for {} more blocks", remaining_blocks))
+                        .into());
+                }
+                LookupTableStatus::Deactivated => {}
+            }
+
+            let transaction = v0_transaction(
+                &authority,
+                &[close_lookup_table(table_pubkey, authority.pubkey(), recipient)],
+                &[],
+                client.get_latest_blockhash().await.map_err(ServiceError::from)?,
+            )?;
+            let result = self.send_and_confirm(cluster, &client, &transaction, commitment).await?;
+            info!(table = %table_pubkey, %recipient, signature = %result.signature, "closed lookup table");
+
+            Ok(Response::new(CloseLookupTableResponse { result: Some(result) }))
+        })
+        .await
+    }
+
+    async fn get_lookup_table(
+        &self,
+        request: Request<GetLookupTableRequest>,
+    ) -> Result<Response<GetLookupTableResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("GetLookupTable", &network, async {
+            authorize(&request, self.auth_required, "GetLookupTable", Some(&network))?;
+            let GetLookupTableRequest {
+                network,
+                lookup_table_address,
+            } = request.into_inner();
+            let (_, client) = self.client(&network)?;
+            let table_pubkey = parse_pubkey("lookup_table_address", &lookup_table_address)?;
+            let table = lookup_table(&client, &table_pubkey, client.commitment()).await?;
+
+            let (state, remaining_blocks) = match lookup_table::status(&client, &table.meta).await? {
+                LookupTableStatus::Activated => (LookupTableState::Active, 0),
+                LookupTableStatus::Deactivating { remaining_blocks } => {
+                    (LookupTableState::Deactivating, remaining_blocks as u64)
+                }
+                LookupTableStatus::Deactivated => (LookupTableState::Deactivated, 0),
+            };
+            let response = GetLookupTableResponse {
+                authority: table.meta.authority.map(|authority| authority.to_string()).unwrap_or_default(),
+                addresses: table.addresses.iter().map(Pubkey::to_string).collect(),
+                state: state.into(),
+                deactivation_slot: if table.is_deactivated() { table.meta.deactivation_slot } else { 0 },
+                remaining_blocks,
+                last_extended_slot: table.meta.last_extended_slot,
+            };
+
+            Ok(Response::new(response))
+        })
+        .await
+    }
+
+    async fn get_account_info(
+        &self,
+        request: Request<GetAccountInfoRequest>,
+    ) -> Result<Response<GetAccountInfoResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("GetAccountInfo", &network, async {
+            authorize(&request, self.auth_required, "GetAccountInfo", Some(&network))?;
+            let GetAccountInfoRequest {
+                network,
+                address,
+                commitment,
+            } = request.into_inner();
+            let (_, client) = self.client(&network)?;
+            let commitment = commitment_config(
+                solana::Commitment::try_from(commitment).unwrap_or_default(),
+                &client,
+            );
+            let pubkey = parse_pubkey("address", &address)?;
+            let response = client
+                .get_account_with_commitment(&pubkey, commitment)
+                .await
+                .map_err(ServiceError::from)?;
+            let account = response
+                .value
+                .ok_or_else(|| ServiceError::AccountNotFound(address.clone()))?;
+
+            // A decode failure still returns the raw account, so callers can inspect it
+            let (layout, decoded, decode_error) = match self.decoders.decode(&account.owner, &account.data) {
+                Ok(Some(decoded)) => (decoded.layout.to_string(), decoded.value.to_string(), String::new()),
+                Ok(None) => Default::default(),
+                Err(err) => (String::new(), String::new(), err),
+            };
+            let response = GetAccountInfoResponse {
+                owner: account.owner.to_string(),
+                lamports: account.lamports,
+                executable: account.executable,
+                rent_epoch: account.rent_epoch,
+                data: BASE64.encode(&account.data),
+                layout,
+                decoded,
+                decode_error,
+                slot: response.context.slot,
+            };
+
+            Ok(Response::new(response))
+        })
+        .await
+    }
+
+    async fn get_transaction_history(
+        &self,
+        request: Request<GetTransactionHistoryRequest>,
+    ) -> Result<Response<GetTransactionHistoryResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("GetTransactionHistory", &network, async {
+            authorize(&request, self.auth_required, "GetTransactionHistory", Some(&network))?;
+            let GetTransactionHistoryRequest {
+                network,
+                address,
+                before,
+                until,
+                limit,
+                commitment,
+            } = request.into_inner();
+            let (_, client) = self.client(&network)?;
+            let commitment = commitment_config(
+                solana::Commitment::try_from(commitment).unwrap_or_default(),
+                &client,
+            );
+            let pubkey = parse_pubkey("address", &address)?;
+            let before = (!before.is_empty()).then(|| parse_signature("before", &before)).transpose()?;
+            let until = (!until.is_empty()).then(|| parse_signature("until", &until)).transpose()?;
+            let limit = match limit as usize {
+                0 => DEFAULT_HISTORY_LIMIT,
+                limit if limit > MAX_HISTORY_LIMIT => {
+                    return Err(ServiceError::InvalidArgument {
+                        field: "limit",
+                        reason: format!("at most {} entries per page", MAX_HISTORY_LIMIT),
+                    }
+                    .into());
+                }
+                limit => limit,
+            };
+
+            let entries = history(&client, &pubkey, before, until, limit, commitment).await?;
+            // A short page means the history, or the range up to `until`, is exhausted
+            let next_before = match entries.last() {
+                Some(last) if entries.len() == limit => last.signature.clone(),
+                _ => String::new(),
+            };
+
+            Ok(Response::new(GetTransactionHistoryResponse { entries, next_before }))
+        })
+        .await
+    }
+
+    async fn get_transaction(
+        &self,
+        request: Request<GetTransactionRequest>,
+    ) -> Result<Response<GetTransactionResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("GetTransaction", &network, async {
+            authorize(&request, self.auth_required, "GetTransaction", Some(&network))?;
+            let GetTransactionRequest {
+                network,
+                signature,
+                commitment,
+            } = request.into_inner();
+            let (_, client) = self.client(&network)?;
+            let commitment = commitment_config(
+                solana::Commitment::try_from(commitment).unwrap_or_default(),
+                &client,
+            );
+            let parsed_signature = parse_signature("signature", &signature)?;
+            let landed = landed(&client, &parsed_signature, commitment)
+                .await?
+                .ok_or_else(|| ServiceError::TransactionNotFound(signature.clone()))?;
+
+            let response = GetTransactionResponse {
+                signature,
+                slot: landed.slot,
+                block_time: landed.block_time.unwrap_or_default(),
+                fee: landed.meta.fee,
+                error: landed.meta.err.as_ref().map(|err| err.to_string()).unwrap_or_default(),
+                compute_units_consumed: Option::from(landed.meta.compute_units_consumed.clone()).unwrap_or_default(),
+                instructions: self.parsers.decode(&landed),
+                logs: Option::from(landed.meta.log_messages.clone()).unwrap_or_default(),
+                balances: landed
+                    .balances()
+                    .map(|(address, pre_lamports, post_lamports)| BalanceChange {
+                        address: address.to_string(),
+                        pre_lamports,
+                        post_lamports,
+                    })
+                    .collect(),
+            };
+
+            Ok(Response::new(response))
+        })
+        .await
+    }
+
+    async fn build_transfer(
+        &self,
+        request: Request<BuildTransferRequest>,
+    ) -> Result<Response<BuildTransferResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("BuildTransfer", &network, async {
+            authorize(&request, self.auth_required, "BuildTransfer", Some(&network))?;
+            let BuildTransferRequest {
+                network,
+                from_address,
+                to_address,
+                amount,
+                compute_budget,
+                nonce_address,
+                nonce_authority,
+                memo,
+            } = request.into_inner();
+            let (_, client) = self.client(&network)?;
+            let from_pubkey = parse_pubkey("from_address", &from_address)?;
+            let to_pubkey = parse_pubkey("to_address", &to_address)?;
+            let nonce = match nonce_address.as_str() {
+                "" => None,
+                nonce_address => Some(Nonce {
+                    address: parse_pubkey("nonce_address", nonce_address)?,
+                    authority: match nonce_authority.as_str() {
+                        "" => from_pubkey,
+                        authority => parse_pubkey("nonce_authority", authority)?,
+                    },
+                }),
+            };
+
+            let transfer = Transfer {
+                from: from_pubkey,
+                to: to_pubkey,
+                amount,
+                compute_budget: compute_budget.as_ref(),
+                nonce,
+                memo: &memo,
+            };
+            let unsigned = transfer.build(&client, &self.fees, client.commitment()).await?;
+            let message = unsigned.message;
+            let fee = message_fee(&client, &message).await?;
+            let signers = message.static_account_keys()[..usize::from(message.header().num_required_signatures)]
+                .iter()
+                .map(Pubkey::to_string)
+                .collect();
+            let response = BuildTransferResponse {
+                message: BASE64.encode(message.serialize()),
+                blockhash: message.recent_blockhash().to_string(),
+                last_valid_block_height: unsigned.last_valid_block_height,
+                signers,
+                fee,
+            };
+
+            Ok(Response::new(response))
+        })
+        .await
+    }
+
+    async fn submit_signed_transaction(
+        &self,
+        request: Request<SubmitSignedTransactionRequest>,
+    ) -> Result<Response<SubmitSignedTransactionResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("SubmitSignedTransaction", &network, async {
+            authorize(&request, self.auth_required, "SubmitSignedTransaction", Some(&network))?;
+            let SubmitSignedTransactionRequest {
+                network,
+                transaction,
+                commitment,
+                no_wait,
+            } = request.into_inner();
+            let (cluster, client) = self.client(&network)?;
+            let commitment = commitment_config(
+                solana::Commitment::try_from(commitment).unwrap_or_default(),
+                &client,
+            );
+            let transaction = parse_transaction("transaction", &transaction)?;
+
+            let result = if no_wait {
+                self.submit(cluster, &client, transaction, commitment).await?
+            } else {
+                self.send_and_confirm(cluster, &client, &transaction, commitment).await?
+            };
+            info!(signature = %result.signature, "submitted signed transaction");
+
+            Ok(Response::new(SubmitSignedTransactionResponse { result: Some(result) }))
+        })
+        .await
+    }
+
+    async fn get_transaction_status(
+        &self,
+        request: Request<TransactionStatusRequest>,
+    ) -> Result<Response<TransactionStatusResponse>, Status> {
+        let network = request.get_ref().network.clone();
+        self.observe("GetTransactionStatus", &network, async {
+            authorize(&request, self.auth_required, "GetTransactionStatus", Some(&network))?;
+            let TransactionStatusRequest {
+                network,
+                signatures,
+            } = request.into_inner();
+            let (cluster, _) = self.client(&network)?;
+            if signatures.len() > MAX_STATUS_SIGNATURES {
+                return Err(ServiceError::InvalidArgument {
+                    field: "signatures",
+                    reason: format!("at most {} signatures per call", MAX_STATUS_SIGNATURES),
+                }
+                .into());
+            }
+
+            let statuses = signatures
+                .iter()
+                .map(|signature| self.tracker.status(&cluster.name, signature))
+                .collect();
+
+            Ok(Response::new(TransactionStatusResponse { statuses }))
+        })
+        .await
+    }
+}
+
+/// Parses addresses to add to a lookup table, dropping duplicates and any already in
+/// `existing`, and checks that the table can hold them.
+fn parse_table_addresses(addresses: &[String], existing: &[Pubkey]) -> Result<Vec<Pubkey>, ServiceError> {
+    let mut parsed: Vec<Pubkey> = Vec::new();
+    for address in addresses {
+        let address = parse_pubkey("addresses", address)?;
+        if !existing.contains(&address) && !parsed.contains(&address) {
+            parsed.push(address);
+        }
+    }
+    if existing.len() + parsed.len() > LOOKUP_TABLE_MAX_ADDRESSES {
+        return Err(ServiceError::InvalidArgument {
+            field: "addresses",
+            reason: format!(
+                "a table holds at most {} addresses, it would hold {}",
+                LOOKUP_TABLE_MAX_ADDRESSES,
+                existing.len() + parsed.len()
+            ),
+        });
+    }
+    Ok(parsed)
+}
+
+fn wallet_message(wallet: WalletInfo) -> Wallet {
+    Wallet {
+        wallet_id: wallet.id,
+        public_key: wallet.public_key,
+        created_at: wallet.created_at,
+        unlocked: wallet.unlocked,
+    }
+}
+
+/// Instructions of a `Greet` call: creating the greeting account when it does not
+/// exist yet, and greeting it.
+struct Greeting {
+    greeted_pubkey: Pubkey,
+    create_account: Option<Instruction>,
+    instruction: Instruction,
+}
+
+async fn greeting(client: &RpcClient, payer: &Pubkey, seed: &str) -> Result<Greeting, ServiceError> {
+    //searching program accounts connected with the program_pubkey
+    let program_pubkey = GREETING_PROGRAM_ID;
+    let greeted_account = client.get_program_accounts(&program_pubkey).await?;
+    debug!(program = %program_pubkey, accounts = greeted_account.len(), "fetched program accounts");
+
+    //creating a new account with the program_pubkey with seed "cauves!"
+    let greeted_pubkey = Pubkey::create_with_seed(payer, seed, &program_pubkey)
+        .map_err(|err| ServiceError::InvalidArgument { field: "seed", reason: err.to_string() })?;
+
+    let instruction = Instruction {
+        program_id: program_pubkey,
+        accounts: vec![
+            solana_program::instruction::AccountMeta::new(greeted_pubkey, false),
+            solana_program::instruction::AccountMeta::new_readonly(*payer, true),
+        ],
+        data: vec![], // No additional data needed for this instruction
+    };
+
+    // //if the account does not exist, create a new account
+    let exists = greeted_account.iter().any(|x: &(Pubkey, solana_sdk::account::Account)| x.0 == greeted_pubkey);
+    let create_account = (!exists).then(|| {
+        system_instruction::create_account_with_seed(
+            payer,
+            &greeted_pubkey,
+            payer,
+            seed,
+            GREETING_ACCOUNT_LAMPORTS,
+            std::mem::size_of::<GreetingAccount>() as u64,
+            &program_pubkey,
+        )
+    });
+
+    Ok(Greeting {
+        greeted_pubkey,
+        create_account,
+        instruction,
+    })
+}
+
+async fn read_greetings(
+    client: &RpcClient,
+    greeted_pubkey: &Pubkey,
+    commitment: CommitmentConfig,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let account_info = client
+        .get_account_with_commitment(greeted_pubkey, commitment)
+        .await?
+        .value
+        .ok_or("Error: cannot find the greeted account")?;
+
+    if account_info.lamports == 0 {
+        return Err("Error: cannot find the greeted account".into());
+    }
+
+    if account_info.data.is_empty() {
+        return Err("Error: account data is empty".into());
+    }
+
+    // Ensure that the data length matches the expected length for GreetingAccount
+    if account_info.data.len() != std::mem::size_of::<GreetingAccount>() {
+        return Err("Error: account data length mismatch".into());
+    }
+
+    let greeting = GreetingAccount::try_from_slice(&account_info.data)?;
+    info!(greeted = %greeted_pubkey, counter = greeting.counter, "greeting counter");
+
+    Ok(greeting.counter)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let config_path =
+        std::env::var("SANDPIT_CONFIG").unwrap_or_else(|_| DEFAULT_CONFIG_PATH.to_string());
+    let config = Config::load(&config_path)?;
+    let _telemetry = telemetry::init(&config.tracing)?;
+    let clusters = ClusterRegistry::from_config(&config.clusters)?;
+    info!(config = %config_path, networks = %clusters.names().join(", "), "loaded networks");
+    let keystore = Keystore::open(&config.keystore.path)?;
+    info!(path = %config.keystore.path.display(), "keystore opened");
+    let metrics = Arc::new(Metrics::new()?);
+    let mut solana_service =
+        MySolanaService::new(clusters, keystore, metrics.clone()).with_fees(config.fees.clone());
+    let shutdown = solana_service.shutdown.clone();
+    let recovery_file = config.shutdown.recovery_file.clone();
+    let carried_over = shutdown::reconcile(&recovery_file, &solana_service.clients).await;
+
+    let authenticator = match &config.auth.credentials {
+        Some(path) => {
+            let authenticator = Arc::new(Authenticator::load(path)?);
+            authenticator.clone().watch(Duration::from_secs(5));
+            solana_service = solana_service.require_auth();
+            info!(path = %path.display(), "loaded credentials");
+            Some(authenticator)
+        }
+        None => {
+            warn!("no [auth] credentials configured, accepting unauthenticated calls");
+            None
+        }
+    };
+
+    let (health_reporter, health_service) = tonic_health::server::health_reporter();
+    HealthMonitor::new(
+        health_reporter,
+        solana_service.clusters.clone(),
+        solana_service.clients.clone(),
+    )
+    .spawn(HEALTH_CHECK_INTERVAL);
+
+    let reflection_service = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(solana::FILE_DESCRIPTOR_SET)
+        .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+        .build_v1()?;
+    let reflection_service_v1alpha = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(solana::FILE_DESCRIPTOR_SET)
+        .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+        .build_v1alpha()?;
+
+    if let Some(metrics_addr) = config.server.metrics_listen {
+        info!("serving metrics on http://{}/metrics", metrics_addr);
+        tokio::spawn(async move {
+            if let Err(err) = metrics::serve(metrics_addr, metrics).await {
+                error!(error = %err, "metrics server failed");
+            }
+        });
+    }
+
+    let addr = match std::env::var("SANDPIT_LISTEN") {
+        Ok(listen) => listen.parse()?,
+        Err(_) => config.server.listen,
+    };
+    let mut server = Server::builder().trace_fn(telemetry::request_span);
+    match &config.server.tls {
+        Some(tls) => {
+            server = server.tls_config(server_tls_config(tls)?)?;
+            let mode = if tls.client_ca.is_some() { "mTLS" } else { "TLS" };
+            info!(%addr, mode, "SolanaServiceServer listening");
+        }
+        None => info!(%addr, mode = "plaintext", "SolanaServiceServer listening"),
+    }
+
+    let signal = {
+        let shutdown = shutdown.clone();
+        async move {
+            shutdown::signal().await;
+            shutdown.begin();
+        }
+    };
+    // Health and reflection stay unauthenticated so load balancers and grpcurl can use them
+    let serve = server
+        .add_service(health_service)
+        .add_service(reflection_service)
+        .add_service(reflection_service_v1alpha)
+        .add_service(SolanaServiceServer::with_interceptor(
+            solana_service,
+            auth::interceptor(authenticator),
+        ))
+        .serve_with_shutdown(addr, signal);
+    tokio::pin!(serve);
+
+    // In-flight calls keep running after the signal until the drain deadline
+    let drain_timeout = Duration::from_secs(config.shutdown.drain_timeout_secs);
+    let served = tokio::select! {
+        result = &mut serve => result,
+        _ = async {
+            shutdown.started().await;
+            info!(timeout = ?drain_timeout, "draining in-flight calls");
+            tokio::time::sleep(drain_timeout).await;
+        } => {
+            warn!(pending = shutdown.pending().len(), "drain deadline reached");
+            Ok(())
+        }
+    };
+
+    // `serve` still owns the unfinished calls here, so their transactions are still tracked
+    if let Err(err) = shutdown.save(&recovery_file, &carried_over) {
+        error!(path = %recovery_file.display(), error = %err, "failed to write recovery file");
+    }
+    served?;
+    info!("shutdown complete");
+
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,87 @@
+use crate::error::ServiceError;
+use solana_sdk::{instruction::Instruction, message::VersionedMessage, pubkey::Pubkey};
+
+/// Longest memo accepted, in bytes. Leaves room for the transfer, compute budget
+/// and nonce instructions it travels with, and for several payouts of a batch.
+pub const MAX_MEMO_LEN: usize = 256;
+
+/// SPL Memo instruction attaching `memo` to a transaction, signed by `signer` so the
+/// memo is attributable to the sender. `None` when the memo is empty.
+pub fn memo_instruction(
+    field: &'static str,
+    memo: &[u8],
+    signer: &Pubkey,
+) -> Result<Option<Instruction>, ServiceError> {
+    if memo.is_empty() {
+        return Ok(None);
+    }
+    if memo.len() > MAX_MEMO_LEN {
+        return Err(ServiceError::InvalidArgument {
+            field,
+            reason: format!(
+                "memo is {} bytes, at most {} are allowed",
+                memo.len(),
+                MAX_MEMO_LEN
+            ),
+        });
+    }
+    // The memo program rejects anything else, so catch it before paying for it
+    if let Err(err) = std::str::from_utf8(memo) {
+        return Err(ServiceError::InvalidArgument {
+            field,
+            reason: format!("memo is not valid UTF-8: {}", err),
+        });
+    }
+    Ok(Some(spl_memo::build_memo(memo, &[signer])))
+}
+
+/// Memos attached to a transaction, by either version of the memo program. The
+/// first version did not check its input, so invalid UTF-8 is replaced rather than
+/// dropped.
+pub fn memos(message: &VersionedMessage) -> Vec<String> {
+    message
+        .instructions()
+        .iter()
+        .filter(|instruction| {
+            message
+                .static_account_keys()
+                .get(usize::from(instruction.program_id_index))
+                .is_some_and(|program_id| {
+                    *program_id == spl_memo::id() || *program_id == spl_memo::v1::id()
+                })
+        })
+        .map(|instruction| String::from_utf8_lossy(&instruction.data).into_owned())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_sdk::{message::Message, system_instruction};
+
+    #[test]
+    fn test_memo_instruction() {
+        let payer = Pubkey::new_unique();
+        assert!(memo_instruction("memo", b"", &payer).unwrap().is_none());
+        assert!(matches!(
+            memo_instruction("memo", &[b'a'; MAX_MEMO_LEN + 1], &payer),
+            Err(ServiceError::InvalidArgument { field: "memo", .. })
+        ));
+        assert!(matches!(
+            memo_instruction("memo", b"inv\xffoice", &payer),
+            Err(ServiceError::InvalidArgument { field: "memo", .. })
+        ));
+
+        let memo = memo_instruction("memo", "invoice #42 ✓".as_bytes(), &payer)
+            .unwrap()
+            .unwrap();
+        let message = VersionedMessage::Legacy(Message::new(
+            &[
+                system_instruction::transfer(&payer, &Pubkey::new_unique(), 1),
+                memo,
+            ],
+            Some(&payer),
+        ));
+        assert_eq!(memos(&message), vec!["invoice #42 ✓".to_string()]);
+    }
+}
@@ -0,0 +1,230 @@
+use axum::{extract::State, http::header, response::IntoResponse, routing::get, Router};
+use prometheus::{
+    exponential_buckets, histogram_opts, opts, Encoder, HistogramVec, IntCounterVec, Registry,
+    TextEncoder,
+};
+use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_rpc_client::http_sender::HttpSender;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tonic::{Code, Status};
+use tracing::Instrument;
+
+/// Prometheus metrics of the service, exported in text format on `/metrics`.
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    request_duration: HistogramVec,
+    rpc_duration: HistogramVec,
+    rpc_errors: IntCounterVec,
+    confirmation_duration: HistogramVec,
+    airdrop_failures: IntCounterVec,
+}
+
+impl Metrics {
+    pub fn new() -> Result<Self, prometheus::Error> {
+        let registry = Registry::new_custom(Some("sandpit".to_string()), None)?;
+
+        let requests = IntCounterVec::new(
+            opts!(
+                "grpc_requests_total",
+                "gRPC calls by method, network and status code"
+            ),
+            &["method", "network", "code"],
+        )?;
+        let request_duration = HistogramVec::new(
+            histogram_opts!("grpc_request_duration_seconds", "gRPC call latency"),
+            &["method", "network"],
+        )?;
+        let rpc_duration = HistogramVec::new(
+            histogram_opts!(
+                "rpc_request_duration_seconds",
+                "Upstream Solana JSON-RPC latency"
+            ),
+            &["cluster", "method"],
+        )?;
+        let rpc_errors = IntCounterVec::new(
+            opts!(
+                "rpc_errors_total",
+                "Failed upstream Solana JSON-RPC requests"
+            ),
+            &["cluster", "method"],
+        )?;
+        let confirmation_duration = HistogramVec::new(
+            histogram_opts!(
+                "transaction_confirmation_seconds",
+                "Time from submitting a transaction until it is confirmed",
+                exponential_buckets(0.25, 2.0, 10)?
+            ),
+            &["cluster"],
+        )?;
+        let airdrop_failures = IntCounterVec::new(
+            opts!(
+                "airdrop_failures_total",
+                "Airdrop requests rejected upstream"
+            ),
+            &["cluster"],
+        )?;
+
+        registry.register(Box::new(requests.clone()))?;
+        registry.register(Box::new(request_duration.clone()))?;
+        registry.register(Box::new(rpc_duration.clone()))?;
+        registry.register(Box::new(rpc_errors.clone()))?;
+        registry.register(Box::new(confirmation_duration.clone()))?;
+        registry.register(Box::new(airdrop_failures.clone()))?;
+
+        Ok(Self {
+            registry,
+            requests,
+            request_duration,
+            rpc_duration,
+            rpc_errors,
+            confirmation_duration,
+            airdrop_failures,
+        })
+    }
+
+    /// Runs a gRPC handler, counting it by status code and recording its latency.
+    /// `network` must be a configured cluster name (or empty) to bound label cardinality.
+    pub async fn observe_request<T, F>(
+        &self,
+        method: &str,
+        network: &str,
+        call: F,
+    ) -> Result<T, Status>
+    where
+        F: Future<Output = Result<T, Status>>,
+    {
+        let start = Instant::now();
+        let result = call.await;
+        let code = match &result {
+            Ok(_) => Code::Ok,
+            Err(status) => status.code(),
+        };
+        self.request_duration
+            .with_label_values(&[method, network])
+            .observe(start.elapsed().as_secs_f64());
+        self.requests
+            .with_label_values(&[method, network, &format!("{:?}", code)])
+            .inc();
+        result
+    }
+
+    pub fn observe_confirmation(&self, cluster: &str, elapsed: Duration) {
+        self.confirmation_duration
+            .with_label_values(&[cluster])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn airdrop_failed(&self, cluster: &str) {
+        self.airdrop_failures.with_label_values(&[cluster]).inc();
+    }
+
+    fn observe_rpc(&self, cluster: &str, method: &str, elapsed: Duration, ok: bool) {
+        self.rpc_duration
+            .with_label_values(&[cluster, method])
+            .observe(elapsed.as_secs_f64());
+        if !ok {
+            self.rpc_errors.with_label_values(&[cluster, method]).inc();
+        }
+    }
+
+    /// Renders all metrics in the Prometheus text exposition format.
+    pub fn encode(&self) -> Result<String, prometheus::Error> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        String::from_utf8(buffer).map_err(|err| prometheus::Error::Msg(err.to_string()))
+    }
+}
+
+/// `RpcSender` that times every JSON-RPC request sent to a cluster, so upstream
+/// latency is recorded for all client calls without instrumenting each call site.
+pub struct MeteredSender {
+    cluster: String,
+    inner: HttpSender,
+    metrics: Arc<Metrics>,
+}
+
+impl MeteredSender {
+    pub fn new(cluster: String, url: String, metrics: Arc<Metrics>) -> Self {
+        Self {
+            cluster,
+            inner: HttpSender::new(url),
+            metrics,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl RpcSender for MeteredSender {
+    async fn send(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> solana_client::client_error::Result<serde_json::Value> {
+        let span = tracing::info_span!("rpc", cluster = %self.cluster, method = %request);
+        let start = Instant::now();
+        let result = self.inner.send(request, params).instrument(span).await;
+        self.metrics.observe_rpc(
+            &self.cluster,
+            &request.to_string(),
+            start.elapsed(),
+            result.is_ok(),
+        );
+        result
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
+
+/// Serves `/metrics` over plain HTTP until the listener fails.
+pub async fn serve(addr: SocketAddr, metrics: Arc<Metrics>) -> std::io::Result<()> {
+    let app = Router::new()
+        .route("/metrics", get(render))
+        .with_state(metrics);
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, app).await
+}
+
+async fn render(State(metrics): State<Arc<Metrics>>) -> impl IntoResponse {
+    match metrics.encode() {
+        Ok(body) => ([(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)], body).into_response(),
+        Err(err) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            err.to_string(),
+        )
+            .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_encode() {
+        let metrics = Metrics::new().unwrap();
+        let result: Result<(), Status> = metrics
+            .observe_request("GetBalance", "devnet", async {
+                Err(Status::unavailable("down"))
+            })
+            .await;
+        assert!(result.is_err());
+        metrics.airdrop_failed("devnet");
+
+        let body = metrics.encode().unwrap();
+        assert!(body.contains(
+            r#"sandpit_grpc_requests_total{code="Unavailable",method="GetBalance",network="devnet"} 1"#
+        ));
+        assert!(body.contains(r#"sandpit_airdrop_failures_total{cluster="devnet"} 1"#));
+    }
+}
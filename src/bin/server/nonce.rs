@@ -0,0 +1,108 @@
+use crate::error::ServiceError;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::nonce_utils::nonblocking::data_from_account;
+use solana_sdk::{
+    commitment_config::CommitmentConfig, hash::Hash, nonce::state::Data, pubkey::Pubkey,
+    transaction::VersionedTransaction,
+};
+
+/// Reads the state of an initialized nonce account.
+pub async fn nonce_data(
+    client: &RpcClient,
+    address: &Pubkey,
+    commitment: CommitmentConfig,
+) -> Result<Data, ServiceError> {
+    let account = client
+        .get_account_with_commitment(address, commitment)
+        .await?
+        .value
+        .ok_or_else(|| ServiceError::InvalidNonceAccount {
+            address: address.to_string(),
+            reason: "account not found".to_string(),
+        })?;
+    data_from_account(&account).map_err(|err| ServiceError::InvalidNonceAccount {
+        address: address.to_string(),
+        reason: err.to_string(),
+    })
+}
+
+/// Nonce account advanced by a durable nonce transaction, `None` for transactions
+/// using a recent blockhash. The nonce account must be one of the static keys; this
+/// server never puts it in a lookup table.
+pub fn nonce_address(transaction: &VersionedTransaction) -> Option<Pubkey> {
+    if !transaction.uses_durable_nonce() {
+        return None;
+    }
+    let instruction = transaction.message.instructions().first()?;
+    let index = *instruction.accounts.first()?;
+    transaction
+        .message
+        .static_account_keys()
+        .get(usize::from(index))
+        .copied()
+}
+
+/// Whether a transaction that has not landed can still land.
+pub async fn can_land(
+    client: &RpcClient,
+    transaction: &VersionedTransaction,
+) -> Result<bool, ServiceError> {
+    is_usable(
+        client,
+        transaction.message.recent_blockhash(),
+        nonce_address(transaction).as_ref(),
+    )
+    .await
+}
+
+/// Whether `blockhash` can still be used by a transaction: it is still recent or,
+/// for durable nonce transactions, the `nonce` account still holds it.
+pub async fn is_usable(
+    client: &RpcClient,
+    blockhash: &Hash,
+    nonce: Option<&Pubkey>,
+) -> Result<bool, ServiceError> {
+    match nonce {
+        Some(address) => {
+            let data = nonce_data(client, address, CommitmentConfig::processed()).await?;
+            Ok(data.blockhash() == *blockhash)
+        }
+        None => Ok(client
+            .is_blockhash_valid(blockhash, CommitmentConfig::processed())
+            .await?),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_sdk::{
+        signature::Keypair, signer::Signer, system_instruction, transaction::Transaction,
+    };
+
+    #[test]
+    fn test_nonce_address() {
+        let payer = Keypair::new();
+        let nonce = Pubkey::new_unique();
+        let transfer = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[
+                system_instruction::advance_nonce_account(&nonce, &payer.pubkey()),
+                transfer.clone(),
+            ],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        );
+        assert_eq!(nonce_address(&transaction.into()), Some(nonce));
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[transfer],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        );
+        assert_eq!(nonce_address(&transaction.into()), None);
+    }
+}
@@ -0,0 +1,37 @@
+use crate::cluster::ClusterRegistry;
+use crate::metrics::{MeteredSender, Metrics};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::RpcClientConfig;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// One long-lived nonblocking RPC client per configured cluster.
+///
+/// Clients keep their HTTP connections alive between requests, so handlers must
+/// borrow them from here instead of building a fresh client per call.
+pub struct RpcClientPool {
+    clients: HashMap<String, Arc<RpcClient>>,
+}
+
+impl RpcClientPool {
+    pub fn new(clusters: &ClusterRegistry, metrics: Arc<Metrics>) -> Self {
+        let clients = clusters
+            .iter()
+            .map(|cluster| {
+                let sender = MeteredSender::new(
+                    cluster.name.clone(),
+                    cluster.rpc_url.clone(),
+                    metrics.clone(),
+                );
+                let client = RpcClient::new_sender(sender, RpcClientConfig::default());
+                (cluster.name.clone(), Arc::new(client))
+            })
+            .collect();
+
+        Self { clients }
+    }
+
+    pub fn get(&self, network: &str) -> Option<Arc<RpcClient>> {
+        self.clients.get(network).cloned()
+    }
+}
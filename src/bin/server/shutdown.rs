@@ -0,0 +1,312 @@
+use crate::error::ServiceError;
+use crate::nonce::is_usable;
+use crate::pool::RpcClientPool;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{hash::Hash, pubkey::Pubkey, signature::Signature};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+/// A submitted transaction whose confirmation has not been observed yet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingTransaction {
+    pub network: String,
+    pub signature: String,
+    pub blockhash: String,
+    /// Set for durable nonce transactions, which stay valid until the nonce advances.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce_account: Option<String>,
+    pub submitted_at: u64,
+}
+
+/// Tracks the server's draining state and the transactions currently waiting for
+/// confirmation, so whatever is still unconfirmed at shutdown can be written to the
+/// recovery file and reconciled on the next start.
+pub struct Shutdown {
+    draining: watch::Sender<bool>,
+    pending: Mutex<HashMap<String, PendingTransaction>>,
+}
+
+/// Removes a transaction from the pending set once its submission finished, with
+/// either outcome.
+pub struct PendingGuard {
+    shutdown: Arc<Shutdown>,
+    signature: String,
+}
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        self.shutdown
+            .pending
+            .lock()
+            .unwrap()
+            .remove(&self.signature);
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self {
+            draining: watch::Sender::new(false),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Stops accepting new calls. Calls already running are left to finish.
+    pub fn begin(&self) {
+        self.draining.send_replace(true);
+    }
+
+    /// Resolves once shutdown has started.
+    pub async fn started(&self) {
+        let mut draining = self.draining.subscribe();
+        // The sender lives in `self`, so the channel cannot close while we wait
+        let _ = draining.wait_for(|draining| *draining).await;
+    }
+
+    /// Refuses new calls once shutdown has started.
+    pub fn check_accepting(&self) -> Result<(), ServiceError> {
+        if *self.draining.borrow() {
+            return Err(ServiceError::ShuttingDown);
+        }
+        Ok(())
+    }
+
+    /// Records a transaction about to be submitted. Keep the guard alive until the
+    /// confirmation outcome is known.
+    pub fn track(
+        self: &Arc<Self>,
+        network: &str,
+        signature: &Signature,
+        blockhash: &Hash,
+        nonce_account: Option<&Pubkey>,
+    ) -> PendingGuard {
+        let submitted_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let pending = PendingTransaction {
+            network: network.to_string(),
+            signature: signature.to_string(),
+            blockhash: blockhash.to_string(),
+            nonce_account: nonce_account.map(Pubkey::to_string),
+            submitted_at,
+        };
+        self.pending
+            .lock()
+            .unwrap()
+            .insert(pending.signature.clone(), pending);
+
+        PendingGuard {
+            shutdown: self.clone(),
+            signature: signature.to_string(),
+        }
+    }
+
+    pub fn pending(&self) -> Vec<PendingTransaction> {
+        let mut pending: Vec<_> = self.pending.lock().unwrap().values().cloned().collect();
+        pending.sort_by_key(|pending| pending.submitted_at);
+        pending
+    }
+
+    /// Writes the still-unconfirmed transactions to `path`, together with anything
+    /// left over from a previous run, or removes the file when nothing is pending.
+    pub fn save(&self, path: &Path, carried_over: &[PendingTransaction]) -> std::io::Result<()> {
+        let mut pending = carried_over.to_vec();
+        pending.extend(self.pending());
+        save_pending(path, &pending)
+    }
+}
+
+pub fn load_pending(path: &Path) -> std::io::Result<Vec<PendingTransaction>> {
+    match fs::read(path) {
+        Ok(contents) => serde_json::from_slice(&contents)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err),
+    }
+}
+
+fn save_pending(path: &Path, pending: &[PendingTransaction]) -> std::io::Result<()> {
+    if pending.is_empty() {
+        return match fs::remove_file(path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        };
+    }
+    let contents = serde_json::to_vec_pretty(pending)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    fs::write(path, contents)
+}
+
+/// Looks up the transactions left in the recovery file by a previous run and logs
+/// their outcome. Returns the ones that are still undecided: unknown to the
+/// cluster while their blockhash is valid, or on a cluster that could not be reached.
+pub async fn reconcile(path: &Path, clients: &RpcClientPool) -> Vec<PendingTransaction> {
+    let pending = match load_pending(path) {
+        Ok(pending) => pending,
+        Err(err) => {
+            error!(path = %path.display(), error = %err, "failed to read recovery file");
+            return Vec::new();
+        }
+    };
+    if pending.is_empty() {
+        return pending;
+    }
+    info!(path = %path.display(), count = pending.len(), "reconciling unconfirmed transactions");
+
+    let mut undecided = Vec::new();
+    for transaction in pending {
+        match reconcile_one(&transaction, clients).await {
+            Ok(true) => {}
+            Ok(false) => undecided.push(transaction),
+            Err(err) => {
+                warn!(
+                    network = %transaction.network,
+                    signature = %transaction.signature,
+                    error = %err,
+                    "could not reconcile transaction"
+                );
+                undecided.push(transaction);
+            }
+        }
+    }
+
+    if let Err(err) = save_pending(path, &undecided) {
+        error!(path = %path.display(), error = %err, "failed to update recovery file");
+    }
+    undecided
+}
+
+/// Returns whether the transaction's fate is known.
+async fn reconcile_one(
+    transaction: &PendingTransaction,
+    clients: &RpcClientPool,
+) -> Result<bool, ServiceError> {
+    let client = clients.get(&transaction.network).ok_or_else(|| {
+        ServiceError::Internal(format!("unknown network {}", transaction.network))
+    })?;
+    let signature = Signature::from_str(&transaction.signature)
+        .map_err(|err| ServiceError::Internal(err.to_string()))?;
+    let blockhash = Hash::from_str(&transaction.blockhash)
+        .map_err(|err| ServiceError::Internal(err.to_string()))?;
+    let nonce_account = transaction
+        .nonce_account
+        .as_deref()
+        .map(Pubkey::from_str)
+        .transpose()
+        .map_err(|err| ServiceError::Internal(err.to_string()))?;
+
+    let status = client
+        .get_signature_statuses_with_history(&[signature])
+        .await?
+        .value
+        .into_iter()
+        .next()
+        .flatten();
+    match status {
+        Some(status) => {
+            match status.err {
+                None => info!(
+                    network = %transaction.network,
+                    %signature,
+                    slot = status.slot,
+                    "transaction from previous run landed"
+                ),
+                Some(err) => warn!(
+                    network = %transaction.network,
+                    %signature,
+                    slot = status.slot,
+                    error = %err,
+                    "transaction from previous run failed"
+                ),
+            }
+            Ok(true)
+        }
+        None => {
+            let valid = is_usable(&client, &blockhash, nonce_account.as_ref()).await?;
+            if !valid {
+                warn!(
+                    network = %transaction.network,
+                    %signature,
+                    "transaction from previous run expired without landing"
+                );
+            }
+            Ok(!valid)
+        }
+    }
+}
+
+/// Resolves on SIGINT or, on Unix, SIGTERM.
+pub async fn signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            error!(error = %err, "failed to listen for SIGINT");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(err) => {
+                error!(error = %err, "failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("received SIGINT"),
+        _ = terminate => info!("received SIGTERM"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pending_recovery_file() {
+        let path =
+            std::env::temp_dir().join(format!("sandpit-pending-{}.json", uuid::Uuid::new_v4()));
+        let shutdown = Arc::new(Shutdown::new());
+        let signature = Signature::new_unique();
+
+        let guard = shutdown.track("devnet", &signature, &Hash::new_unique(), None);
+        let confirmed = shutdown.track(
+            "devnet",
+            &Signature::new_unique(),
+            &Hash::new_unique(),
+            None,
+        );
+        drop(confirmed);
+
+        shutdown.begin();
+        assert!(matches!(
+            shutdown.check_accepting(),
+            Err(ServiceError::ShuttingDown)
+        ));
+        shutdown.save(&path, &[]).unwrap();
+        drop(guard);
+
+        let pending = load_pending(&path).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].signature, signature.to_string());
+
+        // Nothing left to recover removes the file
+        shutdown.save(&path, &[]).unwrap();
+        assert!(!path.exists());
+        assert!(load_pending(&path).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,182 @@
+use crate::error::ServiceError;
+use crate::solana::{BalanceChange, Simulation};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig};
+use solana_sdk::transaction::VersionedTransaction;
+
+/// Runs a transaction through `simulateTransaction` instead of submitting it.
+///
+/// Balances are reported for every account the message lists itself, leaving out
+/// any loaded from lookup tables: the current lamports as `pre_lamports` and the
+/// simulated post-state as `post_lamports`.
+pub async fn simulate(
+    client: &RpcClient,
+    transaction: &VersionedTransaction,
+) -> Result<Simulation, ServiceError> {
+    let addresses = transaction.message.static_account_keys();
+    let pre_accounts = client.get_multiple_accounts(addresses).await?;
+    if pre_accounts.len() != addresses.len() {
+        return Err(ServiceError::Internal(format!(
+            "requested {} accounts, received {}",
+            addresses.len(),
+            pre_accounts.len()
+        )));
+    }
+
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        commitment: Some(client.commitment()),
+        accounts: Some(RpcSimulateTransactionAccountsConfig {
+            encoding: None,
+            addresses: addresses.iter().map(|address| address.to_string()).collect(),
+        }),
+        ..RpcSimulateTransactionConfig::default()
+    };
+    let result = client
+        .simulate_transaction_with_config(transaction, config)
+        .await?
+        .value;
+
+    let post_accounts = result.accounts.unwrap_or_default();
+    let balances = addresses
+        .iter()
+        .enumerate()
+        .map(|(index, address)| {
+            let pre_lamports = pre_accounts[index]
+                .as_ref()
+                .map_or(0, |account| account.lamports);
+            let post_lamports = match post_accounts.get(index) {
+                Some(Some(account)) => account.lamports,
+                Some(None) => 0,
+                // No post-state when the simulation failed before execution
+                None => pre_lamports,
+            };
+            BalanceChange {
+                address: address.to_string(),
+                pre_lamports,
+                post_lamports,
+            }
+        })
+        .collect();
+
+    Ok(Simulation {
+        logs: result.logs.unwrap_or_default(),
+        units_consumed: result.units_consumed.unwrap_or_default(),
+        error: result.err.map(|err| err.to_string()).unwrap_or_default(),
+        balances,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::{json, Value};
+    use solana_account_decoder::{encode_ui_account, UiAccountEncoding};
+    use solana_client::rpc_request::RpcRequest;
+    use solana_rpc_client::mock_sender::Mocks;
+    use solana_sdk::{
+        account::Account,
+        hash::Hash,
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        system_instruction, system_program,
+        transaction::Transaction,
+    };
+
+    fn account(address: &Pubkey, lamports: u64) -> Value {
+        let account = Account {
+            lamports,
+            owner: system_program::id(),
+            ..Account::default()
+        };
+        json!(encode_ui_account(address, &account, UiAccountEncoding::Base64, None, None))
+    }
+
+    #[tokio::test]
+    async fn test_simulate() {
+        let payer = Keypair::new();
+        let recipient = Pubkey::new_unique();
+        let transaction: VersionedTransaction = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(&payer.pubkey(), &recipient, 100)],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        )
+        .into();
+        let pre_accounts = json!({
+            "context": { "slot": 1 },
+            "value": [
+                account(&payer.pubkey(), 1000),
+                Value::Null,
+                account(&system_program::id(), 1),
+            ],
+        });
+
+        let mut mocks = Mocks::new();
+        mocks.insert(RpcRequest::GetMultipleAccounts, pre_accounts.clone());
+        mocks.insert(
+            RpcRequest::SimulateTransaction,
+            json!({
+                "context": { "slot": 1 },
+                "value": {
+                    "err": null,
+                    "logs": ["Program 11111111111111111111111111111111 success"],
+                    "accounts": [
+                        account(&payer.pubkey(), 895),
+                        account(&recipient, 100),
+                        account(&system_program::id(), 1),
+                    ],
+                    "unitsConsumed": 150,
+                },
+            }),
+        );
+        let client = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+        let simulation = simulate(&client, &transaction).await.unwrap();
+        assert_eq!(simulation.units_consumed, 150);
+        assert_eq!(simulation.logs.len(), 1);
+        assert!(simulation.error.is_empty());
+        let balances: Vec<_> = simulation
+            .balances
+            .iter()
+            .map(|balance| {
+                (
+                    balance.address.clone(),
+                    balance.pre_lamports,
+                    balance.post_lamports,
+                )
+            })
+            .collect();
+        assert_eq!(
+            balances,
+            vec![
+                (payer.pubkey().to_string(), 1000, 895),
+                (recipient.to_string(), 0, 100),
+                (system_program::id().to_string(), 1, 1),
+            ]
+        );
+
+        // Transactions failing before execution keep their current balances
+        let mut mocks = Mocks::new();
+        mocks.insert(RpcRequest::GetMultipleAccounts, pre_accounts);
+        mocks.insert(
+            RpcRequest::SimulateTransaction,
+            json!({
+                "context": { "slot": 1 },
+                "value": { "err": "AccountNotFound", "logs": [], "accounts": null },
+            }),
+        );
+        let client = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+        let simulation = simulate(&client, &transaction).await.unwrap();
+        assert!(!simulation.error.is_empty());
+        assert_eq!(simulation.units_consumed, 0);
+        assert_eq!(simulation.balances[0].post_lamports, 1000);
+
+        // The default mock returns two accounts for the three the message lists
+        let client = RpcClient::new_mock("succeeds".to_string());
+        assert!(matches!(
+            simulate(&client, &transaction).await,
+            Err(ServiceError::Internal(_))
+        ));
+    }
+}
@@ -0,0 +1,94 @@
+use crate::config::{LogFormat, TracingConfig};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
+use tonic::codegen::http;
+use tracing::Span;
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+use uuid::Uuid;
+
+/// Metadata key carrying the caller's request ID. One is generated when absent.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+const SERVICE_NAME: &str = "solana-sandpit";
+
+/// Flushes pending OTLP spans when dropped at the end of `main`.
+pub struct TelemetryGuard {
+    provider: Option<TracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            if let Err(err) = provider.shutdown() {
+                eprintln!("Failed to flush OTLP spans: {}", err);
+            }
+        }
+    }
+}
+
+/// Installs the global subscriber. The filter is taken from `RUST_LOG` and falls
+/// back to the configured one; spans are exported over OTLP/gRPC when an endpoint
+/// is configured.
+pub fn init(config: &TracingConfig) -> Result<TelemetryGuard, Box<dyn std::error::Error>> {
+    let filter =
+        EnvFilter::try_from_default_env().or_else(|_| EnvFilter::try_new(&config.filter))?;
+    let output = match config.format {
+        LogFormat::Json => fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .boxed(),
+        LogFormat::Text => fmt::layer().boxed(),
+    };
+
+    let provider =
+        match &config.otlp_endpoint {
+            Some(endpoint) => Some(
+                opentelemetry_otlp::new_pipeline()
+                    .tracing()
+                    .with_exporter(
+                        opentelemetry_otlp::new_exporter()
+                            .tonic()
+                            .with_endpoint(endpoint),
+                    )
+                    .with_trace_config(opentelemetry_sdk::trace::Config::default().with_resource(
+                        Resource::new([KeyValue::new("service.name", SERVICE_NAME)]),
+                    ))
+                    .install_batch(runtime::Tokio)?,
+            ),
+            None => None,
+        };
+    let otlp = provider
+        .as_ref()
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME)));
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(output)
+        .with(otlp)
+        .try_init()?;
+
+    Ok(TelemetryGuard { provider })
+}
+
+/// Span covering one gRPC call, installed with `Server::trace_fn`. `network`,
+/// `principal` and `code` are recorded by the handler once they are known.
+pub fn request_span(request: &http::Request<()>) -> Span {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    tracing::info_span!(
+        "grpc",
+        request_id = %request_id,
+        path = %request.uri().path(),
+        network = tracing::field::Empty,
+        principal = tracing::field::Empty,
+        code = tracing::field::Empty,
+    )
+}
@@ -0,0 +1,141 @@
+use crate::config::TlsConfig;
+use std::fs;
+use tonic::transport::{Certificate, Identity, ServerTlsConfig};
+
+/// Builds the rustls server configuration from the PEM files named in `[server.tls]`.
+pub fn server_tls_config(config: &TlsConfig) -> Result<ServerTlsConfig, std::io::Error> {
+    let cert = fs::read(&config.cert)?;
+    let key = fs::read(&config.key)?;
+    let mut tls = ServerTlsConfig::new().identity(Identity::from_pem(cert, key));
+
+    if let Some(client_ca) = &config.client_ca {
+        tls = tls
+            .client_ca_root(Certificate::from_pem(fs::read(client_ca)?))
+            .client_auth_optional(config.client_auth_optional);
+    }
+
+    Ok(tls)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cluster::ClusterRegistry;
+    use crate::config::Config;
+    use crate::keystore::Keystore;
+    use crate::metrics::Metrics;
+    use crate::solana::solana_service_client::SolanaServiceClient;
+    use crate::solana::solana_service_server::SolanaServiceServer;
+    use crate::solana::ListNetworksRequest;
+    use crate::MySolanaService;
+    use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair};
+    use std::path::Path;
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::transport::{Channel, ClientTlsConfig, Server};
+
+    struct Pki {
+        ca: String,
+        server: (String, String),
+        client: (String, String),
+    }
+
+    fn generate_pki() -> Pki {
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+
+        let issue = |name: &str| {
+            let key = KeyPair::generate().unwrap();
+            let cert = CertificateParams::new(vec![name.to_string()])
+                .unwrap()
+                .signed_by(&key, &ca, &ca_key)
+                .unwrap();
+            (cert.pem(), key.serialize_pem())
+        };
+        let server = issue("localhost");
+        let client = issue("client");
+
+        Pki {
+            ca: ca.pem(),
+            server,
+            client,
+        }
+    }
+
+    async fn serve(dir: &Path, pki: &Pki) -> String {
+        fs::write(dir.join("server.pem"), &pki.server.0).unwrap();
+        fs::write(dir.join("server.key"), &pki.server.1).unwrap();
+        fs::write(dir.join("ca.pem"), &pki.ca).unwrap();
+        let config = Config::from_toml(&format!(
+            r#"
+            [server.tls]
+            cert = "{dir}/server.pem"
+            key = "{dir}/server.key"
+            client_ca = "{dir}/ca.pem"
+            "#,
+            dir = dir.display()
+        ))
+        .unwrap();
+        let tls = server_tls_config(config.server.tls.as_ref().unwrap()).unwrap();
+
+        let clusters = ClusterRegistry::from_config(&Config::default().clusters).unwrap();
+        let keystore = Keystore::open(dir.join("keystore")).unwrap();
+        let service = MySolanaService::new(clusters, keystore, Arc::new(Metrics::new().unwrap()));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(
+            Server::builder()
+                .tls_config(tls)
+                .unwrap()
+                .add_service(SolanaServiceServer::new(service))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+
+        format!("https://localhost:{}", port)
+    }
+
+    #[tokio::test]
+    async fn test_mutual_tls() {
+        let dir = std::env::temp_dir().join(format!("sandpit-tls-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let pki = generate_pki();
+        let url = serve(&dir, &pki).await;
+
+        let ca = Certificate::from_pem(&pki.ca);
+        let identity = Identity::from_pem(&pki.client.0, &pki.client.1);
+        let channel = Channel::from_shared(url.clone())
+            .unwrap()
+            .tls_config(ClientTlsConfig::new().ca_certificate(ca.clone()).identity(identity))
+            .unwrap()
+            .connect()
+            .await
+            .unwrap();
+        let response = SolanaServiceClient::new(channel)
+            .list_networks(ListNetworksRequest {})
+            .await
+            .unwrap();
+        assert!(!response.into_inner().networks.is_empty());
+
+        // Without a client certificate the handshake must be rejected
+        let anonymous = Channel::from_shared(url)
+            .unwrap()
+            .tls_config(ClientTlsConfig::new().ca_certificate(ca))
+            .unwrap()
+            .connect()
+            .await;
+        let rejected = match anonymous {
+            Err(_) => true,
+            Ok(channel) => SolanaServiceClient::new(channel)
+                .list_networks(ListNetworksRequest {})
+                .await
+                .is_err(),
+        };
+        assert!(rejected);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,325 @@
+use crate::confirmation::{rebroadcast, signature_status};
+use crate::metrics::Metrics;
+use crate::nonce::can_land;
+use crate::shutdown::PendingGuard;
+use crate::solana::{SignatureStatus, TransactionState};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, transaction::VersionedTransaction};
+use solana_transaction_status::TransactionStatus;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn, Instrument};
+
+/// How often a followed transaction's status is checked. Transactions the cluster
+/// has not seen yet are re-sent at the same pace.
+pub const REBROADCAST_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Longest a transaction is followed. Blockhashes expire well before, but a dropped
+/// durable nonce transaction stays valid until its nonce is advanced.
+const MAX_FOLLOW_DURATION: Duration = Duration::from_secs(10 * 60);
+
+/// Failed status or blockhash checks in a row after which a transaction is no
+/// longer followed. About two minutes, well past the validity of a blockhash.
+const MAX_CONSECUTIVE_ERRORS: u32 = 60;
+
+/// How long the outcome of a transaction is kept once it is final.
+const RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// Outcomes of the transactions this server submitted, keyed by signature, so
+/// `GetTransactionStatus` can answer without going to the cluster.
+pub struct Tracker {
+    metrics: Arc<Metrics>,
+    transactions: Mutex<HashMap<String, Tracked>>,
+}
+
+struct Tracked {
+    network: String,
+    state: TransactionState,
+    slot: u64,
+    error: String,
+    rebroadcasts: u32,
+    memos: Vec<String>,
+    updated_at: Instant,
+}
+
+impl Tracker {
+    pub fn new(metrics: Arc<Metrics>) -> Self {
+        Self {
+            metrics,
+            transactions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records a transaction that was just submitted on `network`, with the memos
+    /// parsed from it.
+    pub fn submitted(&self, network: &str, signature: &str, memos: Vec<String>) {
+        let mut transactions = self.transactions.lock().unwrap();
+        // Finished transactions are dropped lazily, whenever a new one comes in
+        transactions.retain(|_, tracked| {
+            !is_final(tracked.state) || tracked.updated_at.elapsed() < RETENTION
+        });
+        transactions.insert(
+            signature.to_string(),
+            Tracked {
+                network: network.to_string(),
+                state: TransactionState::Pending,
+                slot: 0,
+                error: String::new(),
+                rebroadcasts: 0,
+                memos,
+                updated_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Records the latest status reported by the cluster.
+    pub fn observed(&self, signature: &str, status: &TransactionStatus) {
+        self.update(signature, |tracked| {
+            tracked.state = state(status);
+            tracked.slot = status.slot;
+            tracked.error = status
+                .err
+                .as_ref()
+                .map(|err| err.to_string())
+                .unwrap_or_default();
+        });
+    }
+
+    pub fn expired(&self, signature: &str) {
+        self.update(signature, |tracked| {
+            tracked.state = TransactionState::Expired
+        });
+    }
+
+    /// Stops reporting a transaction as pending once its outcome can no longer be
+    /// learned from the cluster.
+    fn abandoned(&self, signature: &str, reason: String) {
+        self.update(signature, |tracked| {
+            tracked.state = TransactionState::Unknown;
+            tracked.error = reason;
+        });
+    }
+
+    fn update(&self, signature: &str, update: impl FnOnce(&mut Tracked)) {
+        if let Some(tracked) = self.transactions.lock().unwrap().get_mut(signature) {
+            update(tracked);
+            tracked.updated_at = Instant::now();
+        }
+    }
+
+    /// Status of a signature submitted on `network`. Anything else is `UNKNOWN`.
+    pub fn status(&self, network: &str, signature: &str) -> SignatureStatus {
+        let transactions = self.transactions.lock().unwrap();
+        match transactions
+            .get(signature)
+            .filter(|tracked| tracked.network == network)
+        {
+            Some(tracked) => SignatureStatus {
+                signature: signature.to_string(),
+                state: tracked.state.into(),
+                slot: tracked.slot,
+                error: tracked.error.clone(),
+                rebroadcasts: tracked.rebroadcasts,
+                memos: tracked.memos.clone(),
+            },
+            None => SignatureStatus {
+                signature: signature.to_string(),
+                state: TransactionState::Unknown.into(),
+                ..SignatureStatus::default()
+            },
+        }
+    }
+
+    /// Follows a submitted transaction in the background until it is finalized,
+    /// fails, its blockhash expires, the cluster keeps failing to answer or it has
+    /// been followed for `MAX_FOLLOW_DURATION`, re-broadcasting it while the cluster
+    /// has not seen it. `pending` is held until it is confirmed, so a shutdown before
+    /// that records the transaction in the recovery file.
+    pub fn spawn(
+        self: &Arc<Self>,
+        network: &str,
+        client: Arc<RpcClient>,
+        transaction: VersionedTransaction,
+        pending: Option<PendingGuard>,
+    ) {
+        let tracker = self.clone();
+        let network = network.to_string();
+        let span = tracing::info_span!(
+            "rebroadcast",
+            cluster = %network,
+            signature = %transaction.signatures[0]
+        );
+        tokio::spawn(
+            async move {
+                tracker
+                    .follow(&network, &client, &transaction, pending)
+                    .await
+            }
+            .instrument(span),
+        );
+    }
+
+    async fn follow(
+        &self,
+        network: &str,
+        client: &RpcClient,
+        transaction: &VersionedTransaction,
+        mut pending: Option<PendingGuard>,
+    ) {
+        let signature = transaction.signatures[0];
+        let key = signature.to_string();
+        let start = Instant::now();
+        let mut errors = 0;
+        loop {
+            if start.elapsed() >= MAX_FOLLOW_DURATION {
+                self.abandoned(
+                    &key,
+                    format!(
+                        "not settled after {} seconds",
+                        MAX_FOLLOW_DURATION.as_secs()
+                    ),
+                );
+                warn!("stopped following transaction that did not settle");
+                return;
+            }
+            if errors == MAX_CONSECUTIVE_ERRORS {
+                self.abandoned(
+                    &key,
+                    format!("status unavailable after {} failed checks", errors),
+                );
+                warn!(errors, "stopped following transaction");
+                return;
+            }
+            tokio::time::sleep(REBROADCAST_INTERVAL).await;
+            let status = match signature_status(client, &signature).await {
+                Ok(status) => status,
+                Err(err) => {
+                    warn!(error = %err, "failed to fetch transaction status");
+                    errors += 1;
+                    continue;
+                }
+            };
+
+            match status {
+                Some(status) => {
+                    errors = 0;
+                    self.observed(&key, &status);
+                    let state = state(&status);
+                    if state != TransactionState::Pending && pending.take().is_some() {
+                        self.metrics.observe_confirmation(network, start.elapsed());
+                    }
+                    if is_final(state) {
+                        info!(
+                            slot = status.slot,
+                            state = state.as_str_name(),
+                            "transaction settled"
+                        );
+                        return;
+                    }
+                }
+                None => {
+                    match can_land(client, transaction).await {
+                        Ok(true) => errors = 0,
+                        Ok(false) => {
+                            self.expired(&key);
+                            warn!("transaction expired without landing");
+                            return;
+                        }
+                        Err(err) => {
+                            warn!(error = %err, "failed to check blockhash");
+                            errors += 1;
+                            continue;
+                        }
+                    }
+                    match rebroadcast(client, transaction).await {
+                        Ok(_) => self.update(&key, |tracked| tracked.rebroadcasts += 1),
+                        Err(err) => warn!(error = %err, "failed to re-broadcast transaction"),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Maps a cluster status onto the states reported to clients. Failures are only
+/// reported once confirmed, since a processed transaction may still be dropped.
+pub fn state(status: &TransactionStatus) -> TransactionState {
+    if !status.satisfies_commitment(CommitmentConfig::confirmed()) {
+        TransactionState::Pending
+    } else if status.err.is_some() {
+        TransactionState::Failed
+    } else if status.satisfies_commitment(CommitmentConfig::finalized()) {
+        TransactionState::Finalized
+    } else {
+        TransactionState::Confirmed
+    }
+}
+
+/// Transactions that are no longer followed, including those given up on.
+pub fn is_final(state: TransactionState) -> bool {
+    matches!(
+        state,
+        TransactionState::Finalized
+            | TransactionState::Expired
+            | TransactionState::Failed
+            | TransactionState::Unknown
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_sdk::transaction::TransactionError;
+    use solana_transaction_status::TransactionConfirmationStatus;
+
+    #[test]
+    fn test_tracked_status() {
+        let tracker = Tracker::new(Arc::new(Metrics::new().unwrap()));
+        tracker.submitted("devnet", "sig", vec!["invoice #42".to_string()]);
+        assert_eq!(
+            tracker.status("devnet", "sig").state(),
+            TransactionState::Pending
+        );
+        // Signatures are only reported on the network they were submitted to
+        assert_eq!(
+            tracker.status("mainnet", "sig").state(),
+            TransactionState::Unknown
+        );
+
+        let mut status = TransactionStatus {
+            slot: 42,
+            confirmations: Some(1),
+            status: Ok(()),
+            err: None,
+            confirmation_status: Some(TransactionConfirmationStatus::Processed),
+        };
+        tracker.observed("sig", &status);
+        assert_eq!(
+            tracker.status("devnet", "sig").state(),
+            TransactionState::Pending
+        );
+
+        status.confirmation_status = Some(TransactionConfirmationStatus::Confirmed);
+        status.err = Some(TransactionError::AccountNotFound);
+        tracker.observed("sig", &status);
+        let reported = tracker.status("devnet", "sig");
+        assert_eq!(reported.state(), TransactionState::Failed);
+        assert_eq!(reported.slot, 42);
+        assert!(!reported.error.is_empty());
+        assert_eq!(reported.memos, vec!["invoice #42".to_string()]);
+
+        tracker.submitted("devnet", "other", Vec::new());
+        tracker.expired("other");
+        assert_eq!(
+            tracker.status("devnet", "other").state(),
+            TransactionState::Expired
+        );
+
+        tracker.submitted("devnet", "lost", Vec::new());
+        tracker.abandoned("lost", "status unavailable".to_string());
+        let reported = tracker.status("devnet", "lost");
+        assert_eq!(reported.state(), TransactionState::Unknown);
+        assert_eq!(reported.error, "status unavailable");
+    }
+}
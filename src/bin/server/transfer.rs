@@ -0,0 +1,226 @@
+use crate::config::FeeConfig;
+use crate::error::ServiceError;
+use crate::fees::compute_budget_instructions;
+use crate::memo::memo_instruction;
+use crate::nonce::nonce_data;
+use crate::solana::ComputeBudget;
+use crate::versioned::v0_message;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig, message::VersionedMessage, pubkey::Pubkey,
+    system_instruction,
+};
+
+/// Durable nonce used in place of a recent blockhash.
+pub struct Nonce {
+    pub address: Pubkey,
+    pub authority: Pubkey,
+}
+
+/// A SOL transfer as built by `SendSol`, and by `BuildTransfer` for offline signing.
+pub struct Transfer<'a> {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub amount: u64,
+    pub compute_budget: Option<&'a ComputeBudget>,
+    pub nonce: Option<Nonce>,
+    /// Attached with the SPL Memo program when not empty.
+    pub memo: &'a [u8],
+}
+
+pub struct UnsignedTransfer {
+    pub message: VersionedMessage,
+    /// Last block height the message can land at, 0 when it uses a durable nonce.
+    pub last_valid_block_height: u64,
+}
+
+impl Transfer<'_> {
+    /// Builds the unsigned v0 message. With a durable nonce its blockhash is the stored
+    /// nonce, and the instruction advancing it comes first, as the runtime requires.
+    pub async fn build(
+        &self,
+        client: &RpcClient,
+        fees: &FeeConfig,
+        commitment: CommitmentConfig,
+    ) -> Result<UnsignedTransfer, ServiceError> {
+        let memo = memo_instruction("memo", self.memo, &self.from)?;
+        let mut instructions = Vec::new();
+        let (blockhash, last_valid_block_height) = match &self.nonce {
+            Some(nonce) => {
+                let data = nonce_data(client, &nonce.address, commitment).await?;
+                if data.authority != nonce.authority {
+                    return Err(ServiceError::InvalidNonceAccount {
+                        address: nonce.address.to_string(),
+                        reason: format!("authority is {}, not {}", data.authority, nonce.authority),
+                    });
+                }
+                instructions.push(system_instruction::advance_nonce_account(
+                    &nonce.address,
+                    &nonce.authority,
+                ));
+                (data.blockhash(), 0)
+            }
+            None => {
+                client
+                    .get_latest_blockhash_with_commitment(commitment)
+                    .await?
+            }
+        };
+
+        instructions.extend(
+            compute_budget_instructions(client, fees, self.compute_budget, &[self.from, self.to])
+                .await?,
+        );
+        instructions.extend(memo);
+        instructions.push(system_instruction::transfer(
+            &self.from,
+            &self.to,
+            self.amount,
+        ));
+
+        Ok(UnsignedTransfer {
+            message: v0_message(&self.from, &instructions, &[], blockhash)?,
+            last_valid_block_height,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+    use solana_account_decoder::{encode_ui_account, UiAccountEncoding};
+    use solana_client::rpc_request::RpcRequest;
+    use solana_rpc_client::mock_sender::{Mocks, PUBKEY};
+    use solana_sdk::{
+        account::Account,
+        compute_budget,
+        hash::Hash,
+        nonce::state::{Data, DurableNonce, State, Versions},
+        system_program,
+    };
+    use std::str::FromStr;
+
+    /// A client whose `getAccountInfo` returns a nonce account owned by `authority`.
+    fn nonce_client(
+        address: &Pubkey,
+        authority: &Pubkey,
+        durable_nonce: DurableNonce,
+    ) -> RpcClient {
+        let state = Versions::new(State::Initialized(Data::new(
+            *authority,
+            durable_nonce,
+            5000,
+        )));
+        let account = Account {
+            lamports: 1_500_000,
+            data: bincode::serialize(&state).unwrap(),
+            owner: system_program::id(),
+            executable: false,
+            rent_epoch: 0,
+        };
+        let account = encode_ui_account(address, &account, UiAccountEncoding::Base64, None, None);
+        let mut mocks = Mocks::new();
+        mocks.insert(
+            RpcRequest::GetAccountInfo,
+            json!({ "context": { "slot": 1 }, "value": account }),
+        );
+        RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks)
+    }
+
+    #[tokio::test]
+    async fn test_build() {
+        let from = Pubkey::new_unique();
+        let to = Pubkey::new_unique();
+        let budget = ComputeBudget {
+            unit_limit: 1000,
+            unit_price: 5,
+            auto_price: false,
+        };
+        let nonce = Nonce {
+            address: Pubkey::new_unique(),
+            authority: from,
+        };
+        let mut transfer = Transfer {
+            from,
+            to,
+            amount: 42,
+            compute_budget: Some(&budget),
+            nonce: Some(nonce),
+            memo: b"invoice",
+        };
+
+        // The stored nonce replaces the blockhash, and advancing it comes first
+        let durable_nonce = DurableNonce::from_blockhash(&Hash::new_unique());
+        let client = nonce_client(
+            &transfer.nonce.as_ref().unwrap().address,
+            &from,
+            durable_nonce,
+        );
+        let unsigned = transfer
+            .build(&client, &FeeConfig::default(), client.commitment())
+            .await
+            .unwrap();
+        let message = &unsigned.message;
+        assert_eq!(message.recent_blockhash(), durable_nonce.as_hash());
+        assert_eq!(unsigned.last_valid_block_height, 0);
+        let programs: Vec<_> = message
+            .instructions()
+            .iter()
+            .map(|instruction| *instruction.program_id(message.static_account_keys()))
+            .collect();
+        assert_eq!(
+            programs,
+            vec![
+                system_program::id(),
+                compute_budget::id(),
+                compute_budget::id(),
+                spl_memo::id(),
+                system_program::id(),
+            ]
+        );
+        let data: Vec<_> = message
+            .instructions()
+            .iter()
+            .map(|instruction| instruction.data.clone())
+            .collect();
+        assert_eq!(
+            data[0],
+            system_instruction::advance_nonce_account(&Pubkey::default(), &from).data
+        );
+        assert_eq!(data[4], system_instruction::transfer(&from, &to, 42).data);
+
+        // Nonces held by another authority are rejected
+        let client = nonce_client(
+            &transfer.nonce.as_ref().unwrap().address,
+            &Pubkey::new_unique(),
+            durable_nonce,
+        );
+        assert!(matches!(
+            transfer
+                .build(&client, &FeeConfig::default(), client.commitment())
+                .await,
+            Err(ServiceError::InvalidNonceAccount { .. })
+        ));
+
+        // Without a nonce the latest blockhash is used
+        transfer.nonce = None;
+        let client = RpcClient::new_mock("succeeds".to_string());
+        let unsigned = transfer
+            .build(&client, &FeeConfig::default(), client.commitment())
+            .await
+            .unwrap();
+        assert_eq!(
+            *unsigned.message.recent_blockhash(),
+            Hash::from_str(PUBKEY).unwrap()
+        );
+        assert_eq!(unsigned.last_valid_block_height, 1234);
+        let message = &unsigned.message;
+        let first = &message.instructions()[0];
+        assert_eq!(
+            *first.program_id(message.static_account_keys()),
+            compute_budget::id()
+        );
+        assert_eq!(message.instructions().len(), 4);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::error::ServiceError;
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0, VersionedMessage},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::VersionedTransaction,
+};
+
+/// Compiles `instructions` paid by `payer` into a v0 message. Accounts held by one of
+/// `lookup_tables` are loaded through it instead of being listed in the message.
+pub fn v0_message(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+    blockhash: Hash,
+) -> Result<VersionedMessage, ServiceError> {
+    v0::Message::try_compile(payer, instructions, lookup_tables, blockhash)
+        .map(VersionedMessage::V0)
+        .map_err(|err| ServiceError::Internal(format!("failed to compile message: {}", err)))
+}
+
+/// Signs `message` with exactly the signers it requires, in any order.
+pub fn sign(
+    message: VersionedMessage,
+    signers: &[&Keypair],
+) -> Result<VersionedTransaction, ServiceError> {
+    VersionedTransaction::try_new(message, signers)
+        .map_err(|err| ServiceError::Internal(format!("failed to sign transaction: {}", err)))
+}
+
+/// Builds a v0 transaction signed by its only signer, `payer`.
+pub fn v0_transaction(
+    payer: &Keypair,
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+    blockhash: Hash,
+) -> Result<VersionedTransaction, ServiceError> {
+    let message = v0_message(&payer.pubkey(), instructions, lookup_tables, blockhash)?;
+    sign(message, &[payer])
+}
+
+/// Size of `message` on the wire once signed. Unsigned signatures take as much
+/// space as real ones.
+pub fn serialized_size(message: &VersionedMessage) -> usize {
+    let transaction = VersionedTransaction {
+        signatures: vec![
+            Signature::default();
+            usize::from(message.header().num_required_signatures)
+        ],
+        message: message.clone(),
+    };
+    bincode::serialized_size(&transaction).map_or(usize::MAX, |size| size as usize)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_sdk::system_instruction;
+
+    #[test]
+    fn test_v0_transaction() {
+        let payer = Keypair::new();
+        let recipients: Vec<_> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let instructions: Vec<_> = recipients
+            .iter()
+            .map(|to| system_instruction::transfer(&payer.pubkey(), to, 1))
+            .collect();
+
+        let plain = v0_transaction(&payer, &instructions, &[], Hash::new_unique()).unwrap();
+        assert!(plain.verify_with_results().into_iter().all(|valid| valid));
+        assert_eq!(plain.message.address_table_lookups(), Some(&[][..]));
+
+        // Recipients in a lookup table take one byte instead of 32
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: recipients,
+        };
+        let looked_up =
+            v0_transaction(&payer, &instructions, &[table], Hash::new_unique()).unwrap();
+        assert_eq!(looked_up.message.address_table_lookups().unwrap().len(), 1);
+        assert!(serialized_size(&looked_up.message) < serialized_size(&plain.message));
+        assert_eq!(
+            serialized_size(&plain.message),
+            bincode::serialized_size(&plain).unwrap() as usize
+        );
+
+        let other = Keypair::new();
+        assert!(sign(plain.message, &[&other]).is_err());
+    }
+}